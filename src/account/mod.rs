@@ -0,0 +1,196 @@
+use crate::client::PolymarketClient;
+use crate::domain::{AssetType, BalanceAllowance, MarketDetails};
+use crate::metrics;
+
+use log::{info, warn};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tokio::time::{sleep, Duration};
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountConfig {
+    pub balance_refresh_secs: u64,
+    pub market_refresh_secs: u64,
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        Self {
+            balance_refresh_secs: 15,
+            market_refresh_secs: 30,
+        }
+    }
+}
+
+/* =======================
+STATE
+======================= */
+
+/// Balances and market status kept fresh in the background, so the
+/// decision and execution path never waits on REST.
+pub struct AccountState {
+    config: AccountConfig,
+    api: Arc<PolymarketClient>,
+
+    usdc: RwLock<Option<BalanceAllowance>>,
+    // token id → balance, for every outcome of the tracked markets
+    tokens: RwLock<HashMap<String, BalanceAllowance>>,
+    markets: RwLock<HashMap<String, MarketDetails>>,
+}
+
+impl AccountState {
+    pub fn new(config: AccountConfig, api: Arc<PolymarketClient>) -> Self {
+        Self {
+            config,
+            api,
+            usdc: RwLock::new(None),
+            tokens: RwLock::new(HashMap::new()),
+            markets: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // ==================================================
+    // READS (MEMORY ONLY)
+    // ==================================================
+    /// USDC orders can actually spend: balance capped by allowance
+    pub async fn available_usdc(&self) -> Option<Decimal> {
+        self.usdc.read().await.map(|u| u.available())
+    }
+
+    pub async fn token_balance(&self, token_id: &str) -> Option<BalanceAllowance> {
+        self.tokens.read().await.get(token_id).copied()
+    }
+
+    pub async fn market(&self, condition_id: &str) -> Option<MarketDetails> {
+        self.markets
+            .read()
+            .await
+            .get(condition_id)
+            .cloned()
+    }
+
+    /// Every tracked market
+    pub async fn markets(&self) -> Vec<MarketDetails> {
+        self.markets
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the market is known to be accepting orders
+    pub async fn accepting_orders(&self, condition_id: &str) -> bool {
+        self.markets
+            .read()
+            .await
+            .get(condition_id)
+            .map(|m| m.accepting_orders)
+            .unwrap_or(false)
+    }
+
+    /// Start keeping a market's status fresh
+    pub async fn track(&self, condition_id: &str) {
+        if self.markets.read().await.contains_key(condition_id) {
+            return;
+        }
+
+        // First fetch happens inline so the market is usable right away
+        match self.api.get_market(condition_id).await {
+            Ok(details) => {
+                self.markets
+                    .write()
+                    .await
+                    .insert(condition_id.to_string(), details);
+            }
+            Err(e) => warn!("Failed to load market {}: {}", condition_id, e),
+        }
+    }
+
+    // ==================================================
+    // BACKGROUND REFRESH
+    // ==================================================
+    pub async fn run(self: Arc<Self>) {
+        info!("🏦 Account state service running");
+
+        let balance_every = Duration::from_secs(self.config.balance_refresh_secs);
+        let market_every = Duration::from_secs(self.config.market_refresh_secs);
+
+        let mut last_balance: Option<Instant> = None;
+        let mut last_markets: Option<Instant> = None;
+
+        loop {
+            if last_balance.map(|t| t.elapsed() >= balance_every).unwrap_or(true) {
+                self.refresh_balance().await;
+                last_balance = Some(Instant::now());
+            }
+
+            if last_markets.map(|t| t.elapsed() >= market_every).unwrap_or(true) {
+                self.refresh_markets().await;
+                last_markets = Some(Instant::now());
+            }
+
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    pub async fn refresh_balance(&self) {
+        match self.api.get_balance_allowance(&AssetType::Collateral).await {
+            Ok(usdc) => {
+                metrics::global()
+                    .usdc_balance
+                    .set(usdc.balance.to_f64().unwrap_or_default());
+
+                let previous = self.usdc.write().await.replace(usdc);
+                if previous.map(|p| p.balance) != Some(usdc.balance) {
+                    info!("💰 USDC balance updated: {}", usdc.balance);
+                }
+            }
+            Err(e) => warn!("Balance refresh failed: {}", e),
+        }
+
+        let token_ids: Vec<String> = self
+            .markets
+            .read()
+            .await
+            .values()
+            .flat_map(|m| m.tokens.iter().map(|t| t.token_id.clone()))
+            .collect();
+
+        for token_id in token_ids {
+            match self
+                .api
+                .get_balance_allowance(&AssetType::Conditional(token_id.clone()))
+                .await
+            {
+                Ok(balance) => {
+                    self.tokens.write().await.insert(token_id, balance);
+                }
+                Err(e) => warn!("Token balance refresh for {} failed: {}", token_id, e),
+            }
+        }
+    }
+
+    async fn refresh_markets(&self) {
+        let ids: Vec<String> = self.markets.read().await.keys().cloned().collect();
+
+        for id in ids {
+            match self.api.get_market(&id).await {
+                Ok(details) => {
+                    self.markets.write().await.insert(id, details);
+                }
+                Err(e) => warn!("Market refresh for {} failed: {}", id, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::cache::PriceCache;
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration};
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakerConfig {
+    /// Consecutive order rejects before Degraded / Halted
+    pub degrade_after_rejects: u32,
+    pub halt_after_rejects: u32,
+    /// Seconds after the last reject before entries are allowed again
+    pub reject_cooldown_secs: u64,
+
+    /// Seconds without any market WS message before Degraded / Halted
+    pub ws_degraded_secs: u64,
+    pub ws_halt_secs: u64,
+
+    /// A halt is persisted here so a restart does not silently resume
+    pub halt_file: Option<PathBuf>,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            degrade_after_rejects: 3,
+            halt_after_rejects: 6,
+            reject_cooldown_secs: 30,
+            ws_degraded_secs: 10,
+            ws_halt_secs: 60,
+            halt_file: Some(PathBuf::from("halted.json")),
+        }
+    }
+}
+
+/* =======================
+STATE
+======================= */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BreakerState {
+    Running,
+    /// No new entries; resting orders, cancels and exits carry on.
+    /// Recovers on its own.
+    Degraded,
+    /// Nothing trades until an operator resumes
+    Halted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TripReason {
+    RepeatedRejects { count: u32 },
+    WsStale { secs: u64 },
+    LossLimit { detail: String },
+    LegImbalance { detail: String },
+    Operator { note: String },
+    Reconciliation { detail: String },
+}
+
+impl fmt::Display for TripReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RepeatedRejects { count } => write!(f, "{} consecutive order rejects", count),
+            Self::WsStale { secs } => write!(f, "no WS messages for {}s", secs),
+            Self::LossLimit { detail } => write!(f, "loss limit hit: {}", detail),
+            Self::LegImbalance { detail } => write!(f, "leg imbalance: {}", detail),
+            Self::Operator { note } => write!(f, "operator: {}", note),
+            Self::Reconciliation { detail } => write!(f, "startup reconciliation: {}", detail),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    pub reason: Option<TripReason>,
+    pub since_unix: u64,
+}
+
+fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/* =======================
+BREAKER
+======================= */
+
+/// Running → Degraded → Halted state machine shared by the monitor and trader.
+/// Subscribers are told about every transition; a transition into Halted is
+/// the signal to cancel every open order.
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    status: watch::Sender<BreakerStatus>,
+    consecutive_rejects: AtomicU32,
+    last_reject_unix: AtomicU64,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        // A persisted halt survives restarts
+        let persisted = config
+            .halt_file
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str::<BreakerStatus>(&s).ok());
+
+        let initial = match persisted {
+            Some(status) => {
+                warn!(
+                    "🛑 Starting HALTED (persisted): {}",
+                    status.reason.as_ref().map(|r| r.to_string()).unwrap_or_default()
+                );
+                status
+            }
+            None => BreakerStatus {
+                state: BreakerState::Running,
+                reason: None,
+                since_unix: now_unix(),
+            },
+        };
+
+        let (status, _) = watch::channel(initial);
+
+        Self {
+            config,
+            status,
+            consecutive_rejects: AtomicU32::new(0),
+            last_reject_unix: AtomicU64::new(0),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.status.borrow().state
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        self.status.borrow().clone()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.state() == BreakerState::Halted
+    }
+
+    /// New positions may be opened (Running only)
+    pub fn allows_entries(&self) -> bool {
+        self.state() == BreakerState::Running
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<BreakerStatus> {
+        self.status.subscribe()
+    }
+
+    /// Trip into Halted. Later trips keep the first reason.
+    pub fn trip(&self, reason: TripReason) {
+        if self.is_halted() {
+            return;
+        }
+
+        error!("🛑 CIRCUIT BREAKER TRIPPED — {}", reason);
+
+        let status = BreakerStatus {
+            state: BreakerState::Halted,
+            reason: Some(reason),
+            since_unix: now_unix(),
+        };
+
+        if let Some(path) = &self.config.halt_file {
+            if let Err(e) = serde_json::to_string_pretty(&status)
+                .map_err(anyhow::Error::from)
+                .and_then(|s| std::fs::write(path, s).map_err(anyhow::Error::from))
+            {
+                warn!("Failed to persist halt to {}: {}", path.display(), e);
+            }
+        }
+
+        self.status.send_replace(status);
+    }
+
+    /// Explicit operator action to leave Halted
+    pub fn resume(&self, operator: &str) {
+        if !self.is_halted() {
+            return;
+        }
+
+        info!("▶️ Circuit breaker reset by {}", operator);
+
+        if let Some(path) = &self.config.halt_file {
+            let _ = std::fs::remove_file(path);
+        }
+
+        self.consecutive_rejects.store(0, Ordering::SeqCst);
+        self.status.send_replace(BreakerStatus {
+            state: BreakerState::Running,
+            reason: None,
+            since_unix: now_unix(),
+        });
+    }
+
+    fn set_degraded(&self, degraded: bool) {
+        let current = self.state();
+
+        let next = match (current, degraded) {
+            (BreakerState::Running, true) => BreakerState::Degraded,
+            (BreakerState::Degraded, false) => BreakerState::Running,
+            _ => return,
+        };
+
+        if next == BreakerState::Degraded {
+            warn!("⚠️ Circuit breaker DEGRADED");
+        } else {
+            info!("✅ Circuit breaker back to RUNNING");
+        }
+
+        self.status.send_replace(BreakerStatus {
+            state: next,
+            reason: None,
+            since_unix: now_unix(),
+        });
+    }
+
+    // ==================================================
+    // INPUTS
+    // ==================================================
+    pub fn record_order_result(&self, accepted: bool) {
+        if accepted {
+            self.consecutive_rejects.store(0, Ordering::SeqCst);
+            self.set_degraded(false);
+            return;
+        }
+
+        let count = self.consecutive_rejects.fetch_add(1, Ordering::SeqCst) + 1;
+        self.last_reject_unix.store(now_unix(), Ordering::SeqCst);
+
+        if count >= self.config.halt_after_rejects {
+            self.trip(TripReason::RepeatedRejects { count });
+        } else if count >= self.config.degrade_after_rejects {
+            self.set_degraded(true);
+        }
+    }
+
+    /// Watch the market feed and trip when it goes quiet. Also lets a
+    /// reject-degraded breaker recover once the cooldown has passed; the
+    /// reject count stays, so the next reject degrades again.
+    pub async fn watch_ws(&self, cache: PriceCache) {
+        let now_ms = || {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis()
+        };
+        // A feed that never delivers counts as silent since we started watching
+        let started_ms = now_ms();
+
+        loop {
+            sleep(Duration::from_secs(1)).await;
+
+            let last_ms = cache.last_message_ms().await.unwrap_or(started_ms);
+            let age_secs = (now_ms().saturating_sub(last_ms) / 1000) as u64;
+
+            if age_secs >= self.config.ws_halt_secs {
+                self.trip(TripReason::WsStale { secs: age_secs });
+                continue;
+            }
+
+            let ws_degraded = age_secs >= self.config.ws_degraded_secs;
+            let rejects_degraded = self.consecutive_rejects.load(Ordering::SeqCst)
+                >= self.config.degrade_after_rejects
+                && now_unix()
+                    < self.last_reject_unix.load(Ordering::SeqCst) + self.config.reject_cooldown_secs;
+
+            self.set_degraded(ws_degraded || rejects_degraded);
+        }
+    }
+}
@@ -1,24 +1,42 @@
+use crate::latency::{Stage, Trace};
 use rust_decimal::Decimal;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
-#[derive(Debug, Clone)]
+/// Buffered change notifications before slow subscribers start lagging
+const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct CachedOrderbook {
     pub bids: Vec<(Decimal, Decimal)>, // (price, size)
     pub asks: Vec<(Decimal, Decimal)>, // (price, size)
+    // False when the feed only gave prices; sizes are then placeholders
+    pub sizes_known: bool,
     pub last_update_ms: u128,
 }
 
+/// Change notification carrying the trace started at WS receive
+#[derive(Debug, Clone)]
+pub struct BookUpdate {
+    pub token_id: String,
+    pub trace: Trace,
+}
+
 #[derive(Clone)]
 pub struct PriceCache {
     inner: Arc<RwLock<HashMap<String, CachedOrderbook>>>,
+    updates: broadcast::Sender<BookUpdate>,
 }
 
 impl PriceCache {
     pub fn new() -> Self {
+        let (updates, _) = broadcast::channel(UPDATE_CHANNEL_CAPACITY);
+
         Self {
             inner: Arc::new(RwLock::new(HashMap::new())),
+            updates,
         }
     }
 
@@ -27,21 +45,64 @@ impl PriceCache {
         token_id: &str,
         bids: Vec<(Decimal, Decimal)>,
         asks: Vec<(Decimal, Decimal)>,
+        sizes_known: bool,
+        mut trace: Trace,
     ) {
-        let mut map = self.inner.write().await;
-        map.insert(
-            token_id.to_string(),
-            CachedOrderbook {
-                bids,
-                asks,
-                last_update_ms: now_ms(),
-            },
-        );
+        {
+            let mut map = self.inner.write().await;
+            map.insert(
+                token_id.to_string(),
+                CachedOrderbook {
+                    bids,
+                    asks,
+                    sizes_known,
+                    last_update_ms: now_ms(),
+                },
+            );
+        }
+
+        trace.mark(Stage::CacheUpdate);
+
+        // No subscribers is fine
+        let _ = self.updates.send(BookUpdate {
+            token_id: token_id.to_string(),
+            trace,
+        });
+    }
+
+    /// Notified after every book change
+    pub fn subscribe(&self) -> broadcast::Receiver<BookUpdate> {
+        self.updates.subscribe()
     }
 
     pub async fn get(&self, token_id: &str) -> Option<CachedOrderbook> {
         self.inner.read().await.get(token_id).cloned()
     }
+
+    /// Every cached book
+    pub async fn books(&self) -> HashMap<String, CachedOrderbook> {
+        self.inner.read().await.clone()
+    }
+
+    /// Time of the most recent update across all tokens
+    pub async fn last_message_ms(&self) -> Option<u128> {
+        self.inner
+            .read()
+            .await
+            .values()
+            .map(|b| b.last_update_ms)
+            .max()
+    }
+
+    /// Last update time per token
+    pub async fn update_times(&self) -> Vec<(String, u128)> {
+        self.inner
+            .read()
+            .await
+            .iter()
+            .map(|(token, b)| (token.clone(), b.last_update_ms))
+            .collect()
+    }
 }
 
 fn now_ms() -> u128 {
@@ -0,0 +1,308 @@
+use crate::account::AccountState;
+use crate::risk::OrderIntent;
+
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CapitalConfig {
+    /// USDC each strategy may commit per 15m window.
+    /// Strategies not listed are limited only by the balance.
+    pub budgets: HashMap<String, f64>,
+}
+
+/* =======================
+REJECTIONS
+======================= */
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapitalRejection {
+    pub strategy: &'static str,
+    pub requested: Decimal,
+    pub available: Decimal,
+}
+
+impl fmt::Display for CapitalRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} needs {} USDC, {} available",
+            self.strategy, self.requested, self.available
+        )
+    }
+}
+
+impl std::error::Error for CapitalRejection {}
+
+/* =======================
+STATE
+======================= */
+
+struct Reservation {
+    strategy: &'static str,
+    condition_id: String,
+    price: Decimal,
+    amount: Decimal,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct CapitalState {
+    window_start: u64,
+    // Signed but not yet acknowledged by the exchange
+    in_flight: HashMap<&'static str, Decimal>,
+    // order_id → USDC held for the unfilled part
+    open: HashMap<String, Reservation>,
+    // Filled this window, by strategy
+    deployed: HashMap<&'static str, Decimal>,
+}
+
+impl CapitalState {
+    fn reserved(&self) -> Decimal {
+        self.in_flight.values().copied().sum::<Decimal>()
+            + self.open.values().map(|r| r.amount).sum::<Decimal>()
+    }
+
+    fn committed(&self, strategy: &str) -> Decimal {
+        self.in_flight.get(strategy).copied().unwrap_or_default()
+            + self
+                .open
+                .values()
+                .filter(|r| r.strategy == strategy)
+                .map(|r| r.amount)
+                .sum::<Decimal>()
+            + self.deployed.get(strategy).copied().unwrap_or_default()
+    }
+
+    fn add_in_flight(&mut self, strategy: &'static str, amount: Decimal) {
+        let held = self.in_flight.entry(strategy).or_default();
+        *held = (*held + amount).max(Decimal::ZERO);
+    }
+}
+
+/// Only buys spend USDC
+fn cost(intent: &OrderIntent) -> Decimal {
+    if intent.side == "SELL" {
+        Decimal::ZERO
+    } else {
+        intent.notional()
+    }
+}
+
+/* =======================
+ALLOCATOR
+======================= */
+
+/// Splits the account's spendable USDC between strategies so concurrent
+/// intents never count the same dollars twice.
+///
+/// Lifecycle mirrors `RiskManager`: `reserve` before signing, then
+/// `on_submitted` / `release`, and `on_cancelled` / `on_filled` afterwards.
+pub struct CapitalAllocator {
+    config: CapitalConfig,
+    account: Arc<AccountState>,
+    state: Mutex<CapitalState>,
+}
+
+impl CapitalAllocator {
+    pub fn new(config: CapitalConfig, account: Arc<AccountState>) -> Self {
+        Self {
+            config,
+            account,
+            state: Mutex::new(CapitalState::default()),
+        }
+    }
+
+    fn budget(&self, strategy: &str) -> Option<Decimal> {
+        self.config
+            .budgets
+            .get(strategy)
+            .and_then(|b| Decimal::from_f64(*b))
+    }
+
+    /// USDC `strategy` could reserve right now
+    pub async fn available(&self, strategy: &str) -> Decimal {
+        let balance = self.account.available_usdc().await.unwrap_or(Decimal::ZERO);
+
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+
+        self.available_locked(&state, balance, strategy)
+    }
+
+    fn available_locked(&self, state: &CapitalState, balance: Decimal, strategy: &str) -> Decimal {
+        let free = (balance - state.reserved()).max(Decimal::ZERO);
+
+        match self.budget(strategy) {
+            Some(budget) => free.min((budget - state.committed(strategy)).max(Decimal::ZERO)),
+            None => free,
+        }
+    }
+
+    /// Hold USDC for every buy leg, all or nothing
+    pub async fn reserve(&self, intents: &[OrderIntent]) -> Result<(), CapitalRejection> {
+        let balance = self.account.available_usdc().await.unwrap_or(Decimal::ZERO);
+
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+
+        let mut requested: HashMap<&'static str, Decimal> = HashMap::new();
+        for intent in intents {
+            *requested.entry(intent.strategy).or_default() += cost(intent);
+        }
+
+        let total: Decimal = requested.values().copied().sum();
+        let free = (balance - state.reserved()).max(Decimal::ZERO);
+
+        for (strategy, amount) in &requested {
+            let available = self.available_locked(&state, balance, strategy);
+
+            if *amount > available || total > free {
+                return Err(CapitalRejection {
+                    strategy,
+                    requested: *amount,
+                    available: available.min(free),
+                });
+            }
+        }
+
+        for (strategy, amount) in requested {
+            state.add_in_flight(strategy, amount);
+        }
+
+        Ok(())
+    }
+
+    /// Give back a leg that never reached the book
+    pub async fn release(&self, intent: &OrderIntent) {
+        self.state
+            .lock()
+            .await
+            .add_in_flight(intent.strategy, -cost(intent));
+    }
+
+    /// The exchange accepted the order; hold its USDC until fill or cancel
+    pub async fn on_submitted(&self, order_id: String, intent: &OrderIntent, ttl: Option<Duration>) {
+        let mut state = self.state.lock().await;
+        let amount = cost(intent);
+
+        state.add_in_flight(intent.strategy, -amount);
+        if !amount.is_zero() {
+            state.open.insert(
+                order_id,
+                Reservation {
+                    strategy: intent.strategy,
+                    condition_id: intent.condition_id.clone(),
+                    price: intent.price,
+                    amount,
+                    expires_at: ttl.map(|d| Instant::now() + d),
+                },
+            );
+        }
+    }
+
+    pub async fn on_cancelled(&self, order_id: &str) {
+        self.state.lock().await.open.remove(order_id);
+    }
+
+    pub async fn on_cancel_all(&self) {
+        self.state.lock().await.open.clear();
+    }
+
+    /// Every order on one market was cancelled
+    pub async fn on_market_cancelled(&self, condition_id: &str) {
+        self.state
+            .lock()
+            .await
+            .open
+            .retain(|_, r| r.condition_id != condition_id);
+    }
+
+    /// Turn the filled part of a reservation into a position
+    pub async fn on_filled(&self, order_id: &str, size: Decimal) {
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+
+        let Some(r) = state.open.get_mut(order_id) else {
+            return;
+        };
+
+        let filled = (r.price * size).min(r.amount);
+        let strategy = r.strategy;
+        r.amount -= filled;
+
+        if r.amount.is_zero() {
+            state.open.remove(order_id);
+        }
+        *state.deployed.entry(strategy).or_default() += filled;
+    }
+
+    /// Re-hold USDC for an order found live on the exchange after a restart
+    pub async fn restore_open(&self, order_id: String, intent: &OrderIntent) {
+        let amount = cost(intent);
+        if amount.is_zero() {
+            return;
+        }
+
+        self.state.lock().await.open.insert(
+            order_id,
+            Reservation {
+                strategy: intent.strategy,
+                condition_id: intent.condition_id.clone(),
+                price: intent.price,
+                amount,
+                expires_at: None,
+            },
+        );
+    }
+
+    /// Reserved and deployed USDC by strategy
+    pub async fn snapshot(&self) -> HashMap<String, (Decimal, Decimal)> {
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+
+        let mut out: HashMap<String, (Decimal, Decimal)> = HashMap::new();
+
+        for (strategy, amount) in &state.in_flight {
+            out.entry(strategy.to_string()).or_default().0 += *amount;
+        }
+        for r in state.open.values() {
+            out.entry(r.strategy.to_string()).or_default().0 += r.amount;
+        }
+        for (strategy, amount) in &state.deployed {
+            out.entry(strategy.to_string()).or_default().1 += *amount;
+        }
+
+        out
+    }
+
+    /// Drop expired orders and reset budgets when the 15m window rolls
+    fn roll(state: &mut CapitalState) {
+        let now = Instant::now();
+        state
+            .open
+            .retain(|_, r| r.expires_at.map(|t| t > now).unwrap_or(true));
+
+        let unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let window = (unix / 900) * 900;
+
+        if state.window_start != window {
+            state.window_start = window;
+            state.deployed.clear();
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use reqwest::StatusCode;
+use std::fmt;
+use std::time::Duration;
+
+pub type ClientResult<T> = std::result::Result<T, ClientError>;
+
+/// Why a Polymarket request failed, so callers can tell "try again"
+/// from "the exchange said no".
+#[derive(Debug, Clone)]
+pub enum ClientError {
+    /// 429 — back off, for `retry_after` if the exchange said so
+    RateLimited { retry_after: Option<Duration> },
+    /// Missing / bad credentials, or the client is read-only
+    Auth(String),
+    /// The exchange refused the request
+    Rejected { code: u16, message: String },
+    /// Network failure, timeout or 5xx — the request may not have landed
+    Transient(String),
+    /// The response didn't match the schema we expect
+    Decode(String),
+}
+
+impl ClientError {
+    /// Safe to send again (only ever done for idempotent requests)
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::RateLimited { .. } | Self::Transient(_))
+    }
+
+    /// The request may have been processed before failing (timeout,
+    /// dropped connection, unreadable answer)
+    pub fn may_have_landed(&self) -> bool {
+        matches!(self, Self::Transient(_) | Self::Decode(_))
+    }
+
+    /// The endpoint doesn't exist on this CLOB
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, Self::Rejected { code: 404 | 405 | 501, .. })
+    }
+
+    pub fn from_status(status: StatusCode, body: String, retry_after: Option<Duration>) -> Self {
+        match status.as_u16() {
+            429 => Self::RateLimited { retry_after },
+            401 | 403 => Self::Auth(body),
+            501 => Self::Rejected {
+                code: 501,
+                message: body,
+            },
+            500..=599 => Self::Transient(format!("{}: {}", status, body)),
+            code => Self::Rejected { code, message: body },
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited {
+                retry_after: Some(d),
+            } => write!(f, "rate limited, retry after {:?}", d),
+            Self::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            Self::Auth(msg) => write!(f, "auth failed: {}", msg),
+            Self::Rejected { code, message } => write!(f, "rejected ({}): {}", code, message),
+            Self::Transient(msg) => write!(f, "transient failure: {}", msg),
+            Self::Decode(msg) => write!(f, "unexpected response: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        if let Some(status) = e.status() {
+            return Self::from_status(status, e.to_string(), None);
+        }
+        if e.is_decode() {
+            return Self::Decode(e.to_string());
+        }
+        Self::Transient(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<rust_decimal::Error> for ClientError {
+    fn from(e: rust_decimal::Error) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
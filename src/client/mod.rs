@@ -1,27 +1,45 @@
+mod error;
+mod rate_limit;
+
+pub use error::{ClientError, ClientResult};
+pub use rate_limit::{Endpoint, RateLimiter};
+
 use crate::domain::*;
-use anyhow::{Context, Result};
+use crate::latency::{Stage, Trace};
 use base64::{engine::general_purpose, Engine as _};
 use hmac::{Hmac, Mac};
-use reqwest::Client;
+use log::warn;
+use reqwest::{Client, RequestBuilder, Response};
 use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 use sha2::Sha256;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// Tries per idempotent request, including the first
+const MAX_ATTEMPTS: u32 = 4;
+const BACKOFF_BASE: Duration = Duration::from_millis(250);
+const BACKOFF_CAP: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct PolymarketClient {
     client: Client,
+    limiter: Arc<RateLimiter>,
     pub gamma_url: String,
     pub clob_url: String,
+    pub data_url: String,
 
     pub api_key: String,
     api_secret: String,
     api_passphrase: String,
 
     pub read_only: bool,
+    signature_type: SignatureType,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -38,56 +56,133 @@ impl PolymarketClient {
     pub fn new(
         gamma_url: String,
         clob_url: String,
+        data_url: String,
         api_key: String,
         api_secret: String,
         api_passphrase: String,
         read_only: bool,
     ) -> Self {
+        // Per-request timeouts come from `Endpoint::timeout`
         let client = Client::builder()
-            .timeout(Duration::from_secs(10))
+            .connect_timeout(Duration::from_secs(3))
             .build()
             .expect("Failed to create HTTP client");
 
         Self {
             client,
+            limiter: Arc::new(RateLimiter::new()),
             gamma_url,
             clob_url,
+            data_url,
             api_key,
             api_secret,
             api_passphrase,
             read_only,
+            signature_type: SignatureType::default(),
         }
     }
 
+    /// How our orders are signed (scopes balance queries)
+    pub fn with_signature_type(mut self, signature_type: SignatureType) -> Self {
+        self.signature_type = signature_type;
+        self
+    }
+
     // ==================================================
-    // USDC BALANCE (REAL – API KEY SCOPE)
+    // TRANSPORT
     // ==================================================
-    pub async fn get_usdc_balance(&self) -> Result<Decimal> {
-    let url = format!("{}/balances/me", self.clob_url);
+    /// Rate-limit, send and classify one request.
+    ///
+    /// Idempotent requests are retried on rate limits and transient
+    /// failures with jittered exponential backoff. Others go out exactly
+    /// once. `build` runs per attempt so auth timestamps stay fresh.
+    async fn send<F>(&self, endpoint: Endpoint, idempotent: bool, build: F) -> ClientResult<Response>
+    where
+        F: Fn() -> ClientResult<RequestBuilder>,
+    {
+        let attempts = if idempotent { MAX_ATTEMPTS } else { 1 };
+        let mut attempt = 0;
 
-    let response = self
-        .client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", self.api_key))
-        .send()
-        .await?;
+        loop {
+            attempt += 1;
+            self.limiter.acquire(endpoint).await;
 
-    if !response.status().is_success() {
-        let text = response.text().await.unwrap_or_default();
-        anyhow::bail!("Balance fetch failed: {}", text);
-    }
+            let error = match build()?.timeout(endpoint.timeout()).send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    let body = response.text().await.unwrap_or_default();
+
+                    ClientError::from_status(status, body, retry_after)
+                }
+                Err(e) => e.into(),
+            };
 
-    let balances: Vec<crate::domain::Balance> = response.json().await?;
+            if attempt >= attempts || !error.is_retryable() {
+                return Err(error);
+            }
 
-    for b in balances {
-        if b.asset.eq_ignore_ascii_case("USDC") {
-            return Ok(b.balance);
+            let delay = backoff(attempt, &error);
+            warn!("{:?} attempt {} failed ({}), retrying in {:?}", endpoint, attempt, error, delay);
+            sleep(delay).await;
         }
     }
 
-    Ok(Decimal::ZERO)
-}
+    fn ensure_writable(&self) -> ClientResult<()> {
+        if self.read_only {
+            return Err(ClientError::Auth("READ-ONLY MODE ENABLED".into()));
+        }
+        Ok(())
+    }
+
+    // ==================================================
+    // BALANCE + ALLOWANCE
+    // ==================================================
+    /// Balance and exchange allowance for USDC or one outcome token
+    pub async fn get_balance_allowance(&self, asset: &AssetType) -> ClientResult<BalanceAllowance> {
+        let path = "/balance-allowance";
+        let url = format!("{}{}", self.clob_url, path);
+        let signature_type = self.signature_type.code().to_string();
 
+        let mut query = vec![("signature_type", signature_type.as_str())];
+        match asset {
+            AssetType::Collateral => query.push(("asset_type", "COLLATERAL")),
+            AssetType::Conditional(token_id) => {
+                query.push(("asset_type", "CONDITIONAL"));
+                query.push(("token_id", token_id.as_str()));
+            }
+        }
+
+        let response = self
+            .send(Endpoint::BalanceAllowance, true, || {
+                Ok(self
+                    .with_l2_auth(self.client.get(&url), "GET", path, "")?
+                    .query(&query))
+            })
+            .await?;
+
+        let raw: RawBalanceAllowance = decode(response).await?;
+
+        // Every spender must cover an order, so the smallest allowance binds
+        let allowance = raw
+            .allowance
+            .iter()
+            .chain(raw.allowances.values())
+            .map(|a| from_token_units(a))
+            .min()
+            .unwrap_or(Decimal::ZERO);
+
+        Ok(BalanceAllowance {
+            balance: from_token_units(&raw.balance),
+            allowance,
+        })
+    }
 
     // ==================================================
     // HMAC SIGNING
@@ -115,95 +210,347 @@ impl PolymarketClient {
         general_purpose::STANDARD.encode(mac.finalize().into_bytes())
     }
 
+    /// Attach L2 (API key) auth headers to a CLOB request
+    fn with_l2_auth(
+        &self,
+        request: RequestBuilder,
+        method: &str,
+        path: &str,
+        body: &str,
+    ) -> ClientResult<RequestBuilder> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| ClientError::Transient(e.to_string()))?
+            .as_secs()
+            .to_string();
+
+        let signature = self.sign_request(method, path, body, &timestamp);
+
+        Ok(request
+            .header("POLY-API-KEY", &self.api_key)
+            .header("POLY-API-SIGNATURE", signature)
+            .header("POLY-API-TIMESTAMP", timestamp)
+            .header("POLY-API-PASSPHRASE", &self.api_passphrase))
+    }
+
     // ==================================================
     // MARKETS
     // ==================================================
-    pub async fn get_market_by_slug(&self, slug: &str) -> Result<Market> {
+    pub async fn get_market_by_slug(&self, slug: &str) -> ClientResult<Market> {
         let url = format!("{}/events/slug/{}", self.gamma_url, slug);
-        let response = self.client.get(&url).send().await?;
-        let json: Value = response.json().await?;
+        let response = self
+            .send(Endpoint::GammaEvents, true, || Ok(self.client.get(&url)))
+            .await?;
+        let json: Value = decode(response).await?;
 
-        json["markets"]
+        let market = json["markets"]
             .as_array()
             .and_then(|m| m.first())
-            .map(|m| serde_json::from_value(m.clone()).unwrap())
-            .context("Market not found")
+            .ok_or_else(|| ClientError::Rejected {
+                code: 404,
+                message: format!("Market not found: {}", slug),
+            })?;
+
+        Ok(serde_json::from_value(market.clone())?)
     }
 
     pub async fn get_market(
         &self,
         condition_id: &str,
-    ) -> Result<MarketDetails> {
+    ) -> ClientResult<MarketDetails> {
         let url = format!("{}/markets/{}", self.clob_url, condition_id);
-        Ok(self.client.get(&url).send().await?.json().await?)
+        let response = self
+            .send(Endpoint::ClobMarkets, true, || Ok(self.client.get(&url)))
+            .await?;
+        decode(response).await
     }
 
     // ==================================================
-    // PRICE
+    // LIQUIDITY REWARDS
     // ==================================================
-    pub async fn get_price(
+    /// Our maker reward earnings for a day (YYYY-MM-DD), all pages
+    pub async fn get_reward_earnings(&self, date: &str) -> ClientResult<Vec<RewardEarning>> {
+        let path = "/rewards/user";
+        let url = format!("{}{}", self.clob_url, path);
+        let mut earnings = Vec::new();
+        let mut cursor = String::new();
+
+        loop {
+            let response = self
+                .send(Endpoint::Rewards, true, || {
+                    Ok(self
+                        .with_l2_auth(self.client.get(&url), "GET", path, "")?
+                        .query(&[("date", date), ("next_cursor", cursor.as_str())]))
+                })
+                .await?;
+            let json: Value = decode(response).await?;
+
+            if let Some(data) = json["data"].as_array() {
+                for item in data {
+                    earnings.push(serde_json::from_value(item.clone())?);
+                }
+            }
+
+            // "LTE=" marks the last page
+            match json["next_cursor"].as_str() {
+                Some(next) if !next.is_empty() && next != "LTE=" => cursor = next.to_string(),
+                _ => break,
+            }
+        }
+
+        Ok(earnings)
+    }
+
+    /// Our current share of each market's reward pool (condition_id → %)
+    pub async fn get_reward_percentages(
         &self,
-        token_id: &str,
-        side: &str,
-    ) -> Result<Decimal> {
-        let url = format!("{}/price", self.clob_url);
-        let params = [("token_id", token_id), ("side", side)];
-
-        let json: Value = self
-            .client
-            .get(&url)
-            .query(&params)
-            .send()
-            .await?
-            .json()
+    ) -> ClientResult<std::collections::HashMap<String, Decimal>> {
+        let path = "/rewards/user/percentages";
+        let url = format!("{}{}", self.clob_url, path);
+
+        let response = self
+            .send(Endpoint::Rewards, true, || {
+                self.with_l2_auth(self.client.get(&url), "GET", path, "")
+            })
             .await?;
+        decode(response).await
+    }
+
+    // ==================================================
+    // ACCOUNT ACTIVITY
+    // ==================================================
+    /// Every page of an L2-authenticated, cursor-paginated CLOB list
+    async fn get_paginated<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> ClientResult<Vec<T>> {
+        let url = format!("{}{}", self.clob_url, path);
+        let mut items = Vec::new();
+        let mut cursor = String::new();
+
+        loop {
+            let response = self
+                .send(Endpoint::Ledger, true, || {
+                    Ok(self
+                        .with_l2_auth(self.client.get(&url), "GET", path, "")?
+                        .query(params)
+                        .query(&[("next_cursor", cursor.as_str())]))
+                })
+                .await?;
+            let json: Value = decode(response).await?;
+
+            if let Some(data) = json["data"].as_array() {
+                for item in data {
+                    items.push(serde_json::from_value(item.clone())?);
+                }
+            }
 
-        let price = json["price"]
-            .as_str()
-            .context("Missing price")?;
+            // "LTE=" marks the last page
+            match json["next_cursor"].as_str() {
+                Some(next) if !next.is_empty() && next != "LTE=" => cursor = next.to_string(),
+                _ => break,
+            }
+        }
+
+        Ok(items)
+    }
 
-        Ok(Decimal::from_str(price)?)
+    /// Our live orders on the book
+    pub async fn get_open_orders(&self) -> ClientResult<Vec<OpenOrder>> {
+        self.get_paginated("/data/orders", &[]).await
+    }
+
+    /// Our trades matched since `after_unix`
+    pub async fn get_trades(&self, after_unix: u64) -> ClientResult<Vec<Trade>> {
+        self.get_paginated("/data/trades", &[("after", after_unix.to_string())])
+            .await
+    }
+
+    /// Current conditional-token holdings of `user` (the proxy wallet)
+    pub async fn get_positions(&self, user: &str) -> ClientResult<Vec<ApiPosition>> {
+        let url = format!("{}/positions", self.data_url);
+
+        let response = self
+            .send(Endpoint::Positions, true, || {
+                Ok(self
+                    .client
+                    .get(&url)
+                    .query(&[("user", user), ("sizeThreshold", "0")]))
+            })
+            .await?;
+        decode(response).await
     }
 
     // ==================================================
     // PLACE SIGNED ORDER (REAL TRADING)
     // ==================================================
+    /// Never retried: a timed-out order may still have been placed
     pub async fn place_signed_order(
         &self,
         payload: &SignedOrderPayload,
-    ) -> Result<OrderResponse> {
-        if self.read_only {
-            anyhow::bail!("READ-ONLY MODE ENABLED");
-        }
+        trace: &mut Trace,
+    ) -> ClientResult<OrderResponse> {
+        self.ensure_writable()?;
 
-        let path = "/orders";
+        let path = "/order";
         let url = format!("{}{}", self.clob_url, path);
         let body = serde_json::to_string(payload)?;
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?
-            .as_secs()
-            .to_string();
+        trace.mark(Stage::HttpSend);
+        let response = self
+            .send(Endpoint::PostOrder, false, || {
+                Ok(self
+                    .with_l2_auth(self.client.post(&url), "POST", path, &body)?
+                    .json(payload))
+            })
+            .await?;
+        trace.mark(Stage::ExchangeAck);
+
+        decode(response).await
+    }
 
-        let signature =
-            self.sign_request("POST", path, &body, &timestamp);
+    /// Send several signed orders in one request. Results line up with
+    /// `payloads`; the outer error means nothing was accepted.
+    /// `ClientError::is_unsupported` tells when the endpoint isn't offered.
+    pub async fn place_orders_batch(
+        &self,
+        payloads: &[SignedOrderPayload],
+        trace: &mut Trace,
+    ) -> ClientResult<Vec<ClientResult<OrderResponse>>> {
+        self.ensure_writable()?;
+
+        let path = "/orders";
+        let url = format!("{}{}", self.clob_url, path);
+        let body = serde_json::to_string(payloads)?;
 
+        trace.mark(Stage::HttpSend);
         let response = self
-            .client
-            .post(&url)
-            .header("POLY-API-KEY", &self.api_key)
-            .header("POLY-API-SIGNATURE", signature)
-            .header("POLY-API-TIMESTAMP", &timestamp)
-            .header("POLY-API-PASSPHRASE", &self.api_passphrase)
-            .json(payload)
-            .send()
+            .send(Endpoint::PostOrders, false, || {
+                Ok(self
+                    .with_l2_auth(self.client.post(&url), "POST", path, &body)?
+                    .json(payloads))
+            })
             .await?;
+        trace.mark(Stage::ExchangeAck);
+
+        let items: Vec<Value> = decode(response).await?;
+        let mut results: Vec<ClientResult<OrderResponse>> = items
+            .into_iter()
+            .map(|item| {
+                let error = item["errorMsg"].as_str().filter(|m| !m.is_empty());
+
+                if item["success"].as_bool() == Some(false) || error.is_some() {
+                    return Err(ClientError::Rejected {
+                        code: 400,
+                        message: error.unwrap_or("unknown error").to_string(),
+                    });
+                }
+                Ok(serde_json::from_value(item)?)
+            })
+            .collect();
 
-        if !response.status().is_success() {
-            let text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Order rejected: {}", text);
+        // A short answer leaves the missing legs unconfirmed
+        while results.len() < payloads.len() {
+            results.push(Err(ClientError::Decode("no result in batch response".into())));
         }
 
-        Ok(response.json().await?)
+        Ok(results)
+    }
+
+    // ==================================================
+    // CANCEL EVERYTHING
+    // ==================================================
+    pub async fn cancel_all(&self) -> ClientResult<Value> {
+        self.ensure_writable()?;
+
+        let path = "/cancel-all";
+        let url = format!("{}{}", self.clob_url, path);
+
+        let response = self
+            .send(Endpoint::CancelAll, true, || {
+                self.with_l2_auth(self.client.delete(&url), "DELETE", path, "")
+            })
+            .await?;
+        decode(response).await
+    }
+
+    // ==================================================
+    // CANCEL ORDERS BY ID
+    // ==================================================
+    pub async fn cancel_orders(&self, order_ids: &[String]) -> ClientResult<Value> {
+        self.ensure_writable()?;
+
+        let path = "/orders";
+        let url = format!("{}{}", self.clob_url, path);
+        let body = serde_json::to_string(order_ids)?;
+
+        let response = self
+            .send(Endpoint::CancelOrders, true, || {
+                Ok(self
+                    .with_l2_auth(self.client.delete(&url), "DELETE", path, &body)?
+                    .json(order_ids))
+            })
+            .await?;
+        decode(response).await
+    }
+
+    // ==================================================
+    // CANCEL RESTING ORDERS FOR A MARKET
+    // ==================================================
+    pub async fn cancel_market_orders(
+        &self,
+        condition_id: &str,
+    ) -> ClientResult<Value> {
+        self.ensure_writable()?;
+
+        let path = "/cancel-market-orders";
+        let url = format!("{}{}", self.clob_url, path);
+        let payload = serde_json::json!({ "market": condition_id });
+        let body = payload.to_string();
+
+        let response = self
+            .send(Endpoint::CancelMarketOrders, true, || {
+                Ok(self
+                    .with_l2_auth(self.client.delete(&url), "DELETE", path, &body)?
+                    .json(&payload))
+            })
+            .await?;
+        decode(response).await
+    }
+}
+
+/// Parse a success body, keeping the start of it when the schema drifted
+async fn decode<T: DeserializeOwned>(response: Response) -> ClientResult<T> {
+    let text = response.text().await?;
+
+    serde_json::from_str(&text).map_err(|e| {
+        let snippet: String = text.chars().take(200).collect();
+        ClientError::Decode(format!("{} in {}", e, snippet))
+    })
+}
+
+/// Exponential backoff with ±50% jitter; honours Retry-After
+fn backoff(attempt: u32, error: &ClientError) -> Duration {
+    if let ClientError::RateLimited {
+        retry_after: Some(d),
+    } = error
+    {
+        return *d;
+    }
+
+    let base = BACKOFF_BASE
+        .saturating_mul(1 << (attempt - 1).min(5))
+        .min(BACKOFF_CAP);
+    base.mul_f64(0.5 + rand::random::<f64>())
+}
+
+/// 6-decimal on-chain units → USDC / shares. Unlimited approvals
+/// overflow `Decimal` and come back as `Decimal::MAX`.
+fn from_token_units(units: &str) -> Decimal {
+    match Decimal::from_str(units) {
+        Ok(v) => v / Decimal::from(1_000_000),
+        Err(_) if !units.is_empty() && units.bytes().all(|b| b.is_ascii_digit()) => Decimal::MAX,
+        Err(_) => Decimal::ZERO,
     }
 }
@@ -0,0 +1,131 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Rate-limited endpoint groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    PostOrder,
+    PostOrders,
+    CancelOrders,
+    CancelAll,
+    CancelMarketOrders,
+    BalanceAllowance,
+    /// /data/orders, /data/trades
+    Ledger,
+    Rewards,
+    ClobMarkets,
+    GammaEvents,
+    Positions,
+}
+
+impl Endpoint {
+    const ALL: [Endpoint; 11] = [
+        Endpoint::PostOrder,
+        Endpoint::PostOrders,
+        Endpoint::CancelOrders,
+        Endpoint::CancelAll,
+        Endpoint::CancelMarketOrders,
+        Endpoint::BalanceAllowance,
+        Endpoint::Ledger,
+        Endpoint::Rewards,
+        Endpoint::ClobMarkets,
+        Endpoint::GammaEvents,
+        Endpoint::Positions,
+    ];
+
+    /// Requests per 10s burst window, per Polymarket's published limits
+    fn per_10s(self) -> u32 {
+        match self {
+            Endpoint::PostOrder => 3_500,
+            Endpoint::PostOrders => 1_000,
+            Endpoint::CancelOrders => 1_000,
+            Endpoint::CancelAll => 250,
+            Endpoint::CancelMarketOrders => 1_000,
+            Endpoint::BalanceAllowance => 200,
+            Endpoint::Ledger => 900,
+            Endpoint::Rewards => 500,
+            Endpoint::ClobMarkets => 250,
+            Endpoint::GammaEvents => 500,
+            Endpoint::Positions => 150,
+        }
+    }
+
+    /// Orders are worthless late; everything else can wait longer
+    pub fn timeout(self) -> Duration {
+        match self {
+            Endpoint::PostOrder | Endpoint::PostOrders => Duration::from_secs(5),
+            _ => Duration::from_secs(10),
+        }
+    }
+}
+
+struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_10s: u32) -> Self {
+        let capacity = per_10s as f64;
+        Self {
+            capacity,
+            per_sec: capacity / 10.0,
+            tokens: capacity,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token, or say how long until one is free
+    fn try_take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+}
+
+/// One token bucket per endpoint group, so a burst of cancels can't
+/// starve order placement
+pub struct RateLimiter {
+    buckets: Vec<Mutex<TokenBucket>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            buckets: Endpoint::ALL
+                .iter()
+                .map(|e| Mutex::new(TokenBucket::new(e.per_10s())))
+                .collect(),
+        }
+    }
+
+    /// Wait until `endpoint` may be called
+    pub async fn acquire(&self, endpoint: Endpoint) {
+        let bucket = &self.buckets[endpoint as usize];
+
+        loop {
+            let wait = match bucket.lock().unwrap().try_take() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            sleep(wait).await;
+        }
+    }
+}
@@ -1,5 +1,19 @@
-use clap::Parser;
+use crate::account::AccountConfig;
+use crate::breaker::BreakerConfig;
+use crate::capital::CapitalConfig;
+use crate::control::ControlConfig;
+use crate::domain::SignatureType;
+use crate::feeds::spot::SpotFeedConfig;
+use crate::metrics::MetricsConfig;
+use crate::portfolio::PortfolioConfig;
+use crate::recovery::RecoveryConfig;
+use crate::model::fair_value::FairValueConfig;
+use crate::rewards::RewardsConfig;
+use crate::risk::RiskConfig;
+use crate::wallet::ctf::CtfConfig;
+use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 /* =======================
@@ -13,6 +27,10 @@ pub struct WalletConfig {
 
     // Polymarket trading wallet (proxy / funder address)
     pub proxy_wallet: String,
+
+    // How orders are signed: eoa, poly_proxy or poly_gnosis_safe
+    #[serde(default)]
+    pub signature_type: SignatureType,
 }
 
 /* =======================
@@ -25,6 +43,15 @@ pub struct Args {
     /// Configuration file path
     #[arg(short, long, default_value = "config.json")]
     pub config: PathBuf,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Approve USDC and conditional tokens for the exchange contracts, then exit
+    SetupAllowances,
 }
 
 /* =======================
@@ -36,6 +63,66 @@ pub struct Config {
     pub polymarket: PolymarketConfig,
     pub trading: TradingConfig,
     pub wallet: WalletConfig,
+
+    // Pre-trade limits every order intent must pass
+    #[serde(default)]
+    pub risk: RiskConfig,
+
+    // Per-strategy USDC budgets
+    #[serde(default)]
+    pub capital: CapitalConfig,
+
+    // Kill switch / circuit breaker thresholds
+    #[serde(default)]
+    pub breaker: BreakerConfig,
+
+    // Book max-age policy and WS heartbeat
+    #[serde(default)]
+    pub staleness: StalenessConfig,
+
+    // Ctrl-C / SIGTERM handling
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+
+    // Fill tracking and position reconciliation
+    #[serde(default)]
+    pub portfolio: PortfolioConfig,
+
+    // State journal and startup reconciliation
+    #[serde(default)]
+    pub recovery: RecoveryConfig,
+
+    // On-chain Conditional Tokens calls (redemption)
+    #[serde(default)]
+    pub ctf: Option<CtfConfig>,
+
+    // Background balance / market-status refresh
+    #[serde(default)]
+    pub account: AccountConfig,
+
+    // Optional spot reference feed (Binance / Coinbase / replay)
+    #[serde(default)]
+    pub spot: Option<SpotFeedConfig>,
+
+    // Fair-value model (requires the spot feed)
+    #[serde(default)]
+    pub fair_value: Option<FairValueConfig>,
+
+    // Passive quoting strategy (disabled when absent)
+    #[serde(default)]
+    pub market_making: Option<MarketMakingConfig>,
+
+    // Liquidity-rewards estimator (runs alongside market making)
+    #[serde(default)]
+    pub rewards: Option<RewardsConfig>,
+
+    // Prometheus /metrics endpoint (disabled when absent)
+    #[serde(default)]
+    pub metrics: Option<MetricsConfig>,
+
+    // Authenticated operator HTTP API (disabled when absent)
+    #[serde(default)]
+    pub control: Option<ControlConfig>,
 }
 
 /* =======================
@@ -48,12 +135,20 @@ pub struct PolymarketConfig {
     pub clob_api_url: String,
     pub ws_url: String,
 
+    // Positions / activity (public data API)
+    #[serde(default = "default_data_api_url")]
+    pub data_api_url: String,
+
     // CLOB API credentials (REQUIRED for real trading)
     pub api_key: Option<String>,
     pub api_secret: Option<String>,
     pub api_passphrase: Option<String>,
 }
 
+fn default_data_api_url() -> String {
+    "https://data-api.polymarket.com".to_string()
+}
+
 /* =======================
 TRADING CONFIG
 ======================= */
@@ -68,6 +163,189 @@ pub struct TradingConfig {
     pub btc_condition_id: Option<String>,
 
     pub check_interval_ms: u64,
+
+    // Time-to-expiry gating for the arbitrage strategy
+    #[serde(default)]
+    pub expiry: ExpiryRules,
+
+    // How arbitrage bundles are sized
+    #[serde(default)]
+    pub sizing: SizingPolicy,
+}
+
+/* =======================
+SIZING POLICY
+======================= */
+
+/// Bundle sizing for the arbitrage strategy. Every mode is capped by
+/// `max_position_size`, available capital, book depth and risk limits.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SizingPolicy {
+    /// floor(max_position_size / cost)
+    #[default]
+    Fixed,
+
+    /// A fraction of the Kelly-optimal stake of available capital
+    Kelly {
+        /// 0.25 = quarter Kelly
+        fraction: f64,
+        /// Chance ETH and BTC resolve the same way (0..1)
+        correlation: f64,
+    },
+
+    /// Size so one bundle's PnL standard deviation is `target_usdc`
+    VolatilityTarget { target_usdc: f64, correlation: f64 },
+}
+
+/* =======================
+EXPIRY RULES
+======================= */
+
+/// Per-strategy rules keyed on seconds remaining in the 15m window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpiryRules {
+    /// No new entries once fewer than this many seconds remain
+    pub no_entry_secs: u64,
+
+    /// While more than this many seconds remain, the window is "early"
+    pub early_window_secs: u64,
+
+    /// Profit threshold applied during the early part of the window
+    pub early_min_profit_threshold: Option<f64>,
+
+    /// Cancel resting orders once fewer than this many seconds remain
+    pub flatten_secs: u64,
+}
+
+impl Default for ExpiryRules {
+    fn default() -> Self {
+        Self {
+            no_entry_secs: 60,
+            early_window_secs: 600,
+            early_min_profit_threshold: None,
+            flatten_secs: 30,
+        }
+    }
+}
+
+/* =======================
+STALENESS CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StalenessConfig {
+    /// Books older than this are stale
+    pub max_book_age_ms: u64,
+
+    /// Per-token overrides of `max_book_age_ms`
+    pub token_max_age_ms: HashMap<String, u64>,
+
+    /// Send a WS ping this often
+    pub ws_ping_interval_secs: u64,
+
+    /// Reconnect when nothing arrives for this long
+    pub ws_idle_timeout_secs: u64,
+}
+
+impl StalenessConfig {
+    pub fn max_age_ms(&self, token_id: &str) -> u64 {
+        self.token_max_age_ms
+            .get(token_id)
+            .copied()
+            .unwrap_or(self.max_book_age_ms)
+    }
+}
+
+impl Default for StalenessConfig {
+    fn default() -> Self {
+        Self {
+            max_book_age_ms: 5_000,
+            token_max_age_ms: HashMap::new(),
+            ws_ping_interval_secs: 10,
+            ws_idle_timeout_secs: 30,
+        }
+    }
+}
+
+/* =======================
+SHUTDOWN CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Max wait for in-flight order requests to finish
+    pub drain_timeout_secs: u64,
+
+    /// Cancel every resting order before exit
+    pub cancel_orders: bool,
+
+    /// Write the final /metrics exposition here
+    pub metrics_snapshot: Option<PathBuf>,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: 10,
+            cancel_orders: true,
+            metrics_snapshot: None,
+        }
+    }
+}
+
+/* =======================
+MARKET MAKING CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarketMakingConfig {
+    /// Shares per quote (raised to the market's rewards min_size)
+    pub quote_size: f64,
+
+    /// Distance from reference price to each quote
+    pub half_spread: f64,
+
+    /// Max shares held per outcome token
+    pub max_position: f64,
+
+    /// Price shift at full inventory (quotes move away from the long side)
+    pub inventory_skew: f64,
+
+    /// Re-quote when the reference moves at least this much
+    pub requote_threshold: f64,
+
+    /// Re-quote at least this often even if nothing moved
+    pub max_quote_age_ms: u64,
+
+    /// Keep quotes within the rewards max_spread
+    pub reward_eligible: bool,
+
+    pub expiry: ExpiryRules,
+}
+
+impl Default for MarketMakingConfig {
+    fn default() -> Self {
+        Self {
+            quote_size: 10.0,
+            half_spread: 0.02,
+            max_position: 100.0,
+            inventory_skew: 0.02,
+            requote_threshold: 0.01,
+            max_quote_age_ms: 30_000,
+            reward_eligible: true,
+            expiry: ExpiryRules {
+                no_entry_secs: 120,
+                early_window_secs: 900,
+                early_min_profit_threshold: None,
+                flatten_secs: 60,
+            },
+        }
+    }
 }
 
 /* =======================
@@ -81,6 +359,7 @@ impl Default for Config {
                 gamma_api_url: "https://gamma-api.polymarket.com".to_string(),
                 clob_api_url: "https://clob.polymarket.com".to_string(),
                 ws_url: "wss://ws-subscriptions-clob.polymarket.com/ws/market".to_string(),
+                data_api_url: default_data_api_url(),
 
                 api_key: None,
                 api_secret: None,
@@ -92,12 +371,30 @@ impl Default for Config {
                 eth_condition_id: None,
                 btc_condition_id: None,
                 check_interval_ms: 1000,
+                expiry: ExpiryRules::default(),
+                sizing: SizingPolicy::default(),
             },
             wallet: WalletConfig {
                 private_key: None,
                 chain_id: 137, // Polygon
                 proxy_wallet: String::new(),
+                signature_type: SignatureType::default(),
             },
+            risk: RiskConfig::default(),
+            capital: CapitalConfig::default(),
+            breaker: BreakerConfig::default(),
+            staleness: StalenessConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            portfolio: PortfolioConfig::default(),
+            recovery: RecoveryConfig::default(),
+            ctf: None,
+            account: AccountConfig::default(),
+            spot: None,
+            fair_value: None,
+            market_making: None,
+            rewards: None,
+            metrics: None,
+            control: None,
         }
     }
 }
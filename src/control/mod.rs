@@ -0,0 +1,380 @@
+use crate::account::AccountState;
+use crate::breaker::{CircuitBreaker, TripReason};
+use crate::capital::CapitalAllocator;
+use crate::cache::PriceCache;
+use crate::config::TradingConfig;
+use crate::execution::Trader;
+use crate::portfolio::Portfolio;
+use crate::risk::RiskManager;
+use crate::strategy::market_maker::MarketMaker;
+use crate::wallet::ctf::{self, CtfClient};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{info, warn};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+/// Strategies an operator can pause by name
+pub const STRATEGIES: [&str; 2] = ["arbitrage", "market_maker"];
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlConfig {
+    /// Keep this on loopback
+    pub listen_addr: SocketAddr,
+    /// Env var holding the bearer token operators must send
+    #[serde(default = "default_token_env")]
+    pub token_env: String,
+}
+
+fn default_token_env() -> String {
+    "CONTROL_API_TOKEN".into()
+}
+
+/* =======================
+RUNTIME CONTROLS
+======================= */
+
+/// Knobs operators can turn without a restart.
+/// Seeded from `TradingConfig`, read on every decision.
+pub struct RuntimeControls {
+    paused: RwLock<BTreeSet<String>>,
+    min_profit_threshold: RwLock<Decimal>,
+    max_position_size: RwLock<f64>,
+}
+
+impl RuntimeControls {
+    pub fn new(config: &TradingConfig) -> Self {
+        Self {
+            paused: RwLock::new(BTreeSet::new()),
+            min_profit_threshold: RwLock::new(
+                Decimal::from_f64(config.min_profit_threshold).unwrap_or(Decimal::ZERO),
+            ),
+            max_position_size: RwLock::new(config.max_position_size),
+        }
+    }
+
+    pub fn is_paused(&self, strategy: &str) -> bool {
+        self.paused.read().unwrap().contains(strategy)
+    }
+
+    pub fn set_paused(&self, strategy: &str, paused: bool) {
+        let mut set = self.paused.write().unwrap();
+        if paused {
+            set.insert(strategy.to_string());
+        } else {
+            set.remove(strategy);
+        }
+    }
+
+    pub fn paused(&self) -> Vec<String> {
+        self.paused.read().unwrap().iter().cloned().collect()
+    }
+
+    pub fn min_profit_threshold(&self) -> Decimal {
+        *self.min_profit_threshold.read().unwrap()
+    }
+
+    pub fn set_min_profit_threshold(&self, v: Decimal) {
+        *self.min_profit_threshold.write().unwrap() = v;
+    }
+
+    pub fn max_position_size(&self) -> f64 {
+        *self.max_position_size.read().unwrap()
+    }
+
+    pub fn set_max_position_size(&self, v: f64) {
+        *self.max_position_size.write().unwrap() = v;
+    }
+}
+
+/* =======================
+SERVER
+======================= */
+
+/// Everything the control plane reads or acts on
+pub struct ControlContext {
+    pub controls: Arc<RuntimeControls>,
+    pub breaker: Arc<CircuitBreaker>,
+    pub trader: Arc<Trader>,
+    pub risk: Arc<RiskManager>,
+    pub account: Arc<AccountState>,
+    pub price_cache: PriceCache,
+    pub market_maker: Option<Arc<MarketMaker>>,
+    pub portfolio: Arc<Portfolio>,
+    pub capital: Arc<CapitalAllocator>,
+    pub ctf: Option<Arc<CtfClient>>,
+    /// Order maker; the proxy wallet unless the CTF client runs as an EOA
+    pub proxy_wallet: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThresholdsRequest {
+    min_profit_threshold: Option<f64>,
+    max_position_size: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BreakerRequest {
+    #[serde(default)]
+    note: Option<String>,
+}
+
+pub async fn serve(config: ControlConfig, ctx: ControlContext) {
+    let token = match std::env::var(&config.token_env) {
+        Ok(t) if !t.is_empty() => t,
+        _ => {
+            warn!("⚠️ Control API disabled — {} not set", config.token_env);
+            return;
+        }
+    };
+
+    let ctx = Arc::new(ctx);
+    let token = Arc::new(token);
+
+    let make_svc = make_service_fn(move |_| {
+        let ctx = ctx.clone();
+        let token = token.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let ctx = ctx.clone();
+                let token = token.clone();
+                async move { Ok::<_, Infallible>(handle(req, &ctx, &token).await) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&config.listen_addr) {
+        Ok(builder) => builder.serve(make_svc),
+        Err(e) => {
+            warn!("⚠️ Control API failed to bind {}: {}", config.listen_addr, e);
+            return;
+        }
+    };
+
+    info!("🎛 Control API on http://{}", config.listen_addr);
+
+    if let Err(e) = server.await {
+        warn!("⚠️ Control API stopped: {}", e);
+    }
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn error(status: StatusCode, msg: impl ToString) -> Response<Body> {
+    respond(status, json!({ "error": msg.to_string() }))
+}
+
+fn authorized(req: &Request<Body>, token: &str) -> bool {
+    req.headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v == token)
+        .unwrap_or(false)
+}
+
+async fn read_json<T: for<'de> Deserialize<'de> + Default>(req: Request<Body>) -> Result<T, String> {
+    let bytes = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if bytes.is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+async fn handle(req: Request<Body>, ctx: &ControlContext, token: &str) -> Response<Body> {
+    if !authorized(&req, token) {
+        return error(StatusCode::UNAUTHORIZED, "missing or bad bearer token");
+    }
+
+    let method = req.method().clone();
+    let path = req.uri().path().trim_end_matches('/').to_string();
+    let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (method, parts.as_slice()) {
+        // ---------------- views ----------------
+        (Method::GET, ["status"]) => respond(
+            StatusCode::OK,
+            json!({
+                "breaker": ctx.breaker.status(),
+                "paused_strategies": ctx.controls.paused(),
+                "min_profit_threshold": ctx.controls.min_profit_threshold(),
+                "max_position_size": ctx.controls.max_position_size(),
+            }),
+        ),
+
+        (Method::GET, ["markets"]) => respond(StatusCode::OK, json!(ctx.account.markets().await)),
+
+        (Method::GET, ["books"]) => respond(StatusCode::OK, json!(ctx.price_cache.books().await)),
+
+        (Method::GET, ["positions"]) => respond(
+            StatusCode::OK,
+            json!({
+                "positions": ctx.portfolio.positions().await,
+                "unrealized_pnl": ctx.portfolio.unrealized_pnl().await,
+            }),
+        ),
+
+        (Method::GET, ["capital"]) => {
+            let by_strategy: Value = ctx
+                .capital
+                .snapshot()
+                .await
+                .into_iter()
+                .map(|(strategy, (reserved, deployed))| {
+                    (strategy, json!({ "reserved": reserved, "deployed": deployed }))
+                })
+                .collect::<serde_json::Map<_, _>>()
+                .into();
+
+            respond(
+                StatusCode::OK,
+                json!({
+                    "available_usdc": ctx.account.available_usdc().await,
+                    "strategies": by_strategy,
+                }),
+            )
+        }
+
+        (Method::GET, ["orders"]) => {
+            let orders: Vec<Value> = ctx
+                .risk
+                .open_orders()
+                .await
+                .into_iter()
+                .map(|(order_id, intent)| json!({ "order_id": order_id, "intent": intent }))
+                .collect();
+            respond(StatusCode::OK, json!(orders))
+        }
+
+        // ---------------- strategies ----------------
+        (Method::POST, ["strategies", name, action @ ("pause" | "resume")]) => {
+            if !STRATEGIES.contains(name) {
+                return error(StatusCode::NOT_FOUND, format!("unknown strategy {}", name));
+            }
+
+            let pause = *action == "pause";
+            ctx.controls.set_paused(name, pause);
+            info!("🎛 Operator {} strategy {}", if pause { "paused" } else { "resumed" }, name);
+
+            respond(StatusCode::OK, json!({ "paused_strategies": ctx.controls.paused() }))
+        }
+
+        // ---------------- thresholds ----------------
+        (Method::POST, ["thresholds"]) => {
+            let body: ThresholdsRequest = match read_json::<Option<ThresholdsRequest>>(req).await {
+                Ok(Some(b)) => b,
+                Ok(None) => return error(StatusCode::BAD_REQUEST, "empty body"),
+                Err(e) => return error(StatusCode::BAD_REQUEST, e),
+            };
+
+            if let Some(v) = body.min_profit_threshold {
+                let Some(v) = Decimal::from_f64(v).filter(|v| *v >= Decimal::ZERO) else {
+                    return error(StatusCode::BAD_REQUEST, "invalid min_profit_threshold");
+                };
+                ctx.controls.set_min_profit_threshold(v);
+                info!("🎛 min_profit_threshold → {}", v);
+            }
+
+            if let Some(v) = body.max_position_size {
+                if !v.is_finite() || v < 0.0 {
+                    return error(StatusCode::BAD_REQUEST, "invalid max_position_size");
+                }
+                ctx.controls.set_max_position_size(v);
+                info!("🎛 max_position_size → {}", v);
+            }
+
+            respond(
+                StatusCode::OK,
+                json!({
+                    "min_profit_threshold": ctx.controls.min_profit_threshold(),
+                    "max_position_size": ctx.controls.max_position_size(),
+                }),
+            )
+        }
+
+        // ---------------- actions ----------------
+        (Method::POST, ["cancel-all"]) => match ctx.trader.cancel_all().await {
+            Ok(()) => {
+                if let Some(mm) = ctx.market_maker.as_ref() {
+                    mm.clear_quotes().await;
+                }
+                respond(StatusCode::OK, json!({ "cancelled": true }))
+            }
+            Err(e) => error(StatusCode::BAD_GATEWAY, e),
+        },
+
+        (Method::POST, ["emergency-cancel"]) => {
+            let Some(client) = ctx.ctf.as_ref() else {
+                return error(StatusCode::SERVICE_UNAVAILABLE, "ctf not configured");
+            };
+            let maker = match client.holder(&ctx.proxy_wallet) {
+                Ok(m) => m,
+                Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+            };
+
+            match ctx.trader.emergency_cancel(client, maker).await {
+                Ok(()) => {
+                    if let Some(mm) = ctx.market_maker.as_ref() {
+                        mm.clear_quotes().await;
+                    }
+                    respond(StatusCode::OK, json!({ "cancelled": true }))
+                }
+                Err(e) => error(StatusCode::BAD_GATEWAY, e),
+            }
+        }
+
+        (Method::POST, ["merge", condition_id]) => {
+            let Some(client) = ctx.ctf.as_ref() else {
+                return error(StatusCode::SERVICE_UNAVAILABLE, "ctf not configured");
+            };
+
+            match ctf::merge_complete_sets(
+                client,
+                &ctx.portfolio,
+                &ctx.account,
+                &ctx.risk,
+                condition_id,
+            )
+            .await
+            {
+                Ok(sets) => respond(StatusCode::OK, json!({ "merged_sets": sets })),
+                Err(e) => error(StatusCode::BAD_GATEWAY, e),
+            }
+        }
+
+        (Method::POST, ["breaker", "trip"]) => {
+            let body: BreakerRequest = read_json(req).await.unwrap_or_default();
+            ctx.breaker.trip(TripReason::Operator {
+                note: body.note.unwrap_or_else(|| "control API".into()),
+            });
+            respond(StatusCode::OK, json!(ctx.breaker.status()))
+        }
+
+        (Method::POST, ["breaker", "reset"]) => {
+            ctx.breaker.resume("control API");
+            respond(StatusCode::OK, json!(ctx.breaker.status()))
+        }
+
+        _ => error(StatusCode::NOT_FOUND, "not found"),
+    }
+}
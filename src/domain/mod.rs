@@ -1,6 +1,6 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use rust_decimal_macros::dec;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Market {
@@ -32,37 +32,19 @@ pub struct Token {
     pub price: Option<Decimal>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderBook {
-    pub bids: Vec<OrderBookEntry>,
-    pub asks: Vec<OrderBookEntry>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderBookEntry {
-    pub price: Decimal,
-    pub size: Decimal,
-}
-
 #[derive(Debug, Clone)]
 pub struct TokenPrice {
     pub token_id: String,
     pub bid: Option<Decimal>,
     pub ask: Option<Decimal>,
-}
-
-impl TokenPrice {
-    pub fn ask_price(&self) -> Decimal {
-        self.ask.unwrap_or(Decimal::ONE)
-    }
-
-    pub fn bid_price(&self) -> Decimal {
-        self.bid.unwrap_or(Decimal::ZERO)
-    }
+    /// Shares resting at the best ask, when the feed reports sizes
+    pub ask_size: Option<Decimal>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderRequest {
+    pub salt: String,
+    pub nonce: String,
     pub token_id: String,
     pub side: String, // "BUY" or "SELL"
     pub size: String,
@@ -73,7 +55,9 @@ pub struct OrderRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderResponse {
+    #[serde(alias = "orderID")]
     pub order_id: Option<String>,
+    #[serde(default)]
     pub status: String,
     pub message: Option<String>,
 }
@@ -92,13 +76,22 @@ pub struct ArbitrageOpportunity {
     pub btc_down_price: Decimal,
     pub total_cost: Decimal,
     pub expected_profit: Decimal,
+    /// Bundles available at the best asks of both legs
+    /// (`None` when the feed doesn't report sizes)
+    pub depth: Option<Decimal>,
     pub eth_up_token_id: String,
     pub btc_down_token_id: String,
+    /// Outcomes actually bought ("Up" / "Down") — the eth_up / btc_down
+    /// names only describe the first bundle shape
+    pub eth_outcome: &'static str,
+    pub btc_outcome: &'static str,
     pub eth_condition_id: String,
     pub btc_condition_id: String,
+    pub trace: crate::latency::Trace,
 }
 
-#[derive(Debug, Clone)]
+/// Arbitrage bundle whose legs were accepted and has not settled yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PendingTrade {
     pub eth_token_id: String,
     pub btc_token_id: String,
@@ -106,7 +99,7 @@ pub struct PendingTrade {
     pub btc_condition_id: String,
     pub investment_amount: f64,
     pub units: f64,
-    pub timestamp: std::time::Instant,
+    pub opened_unix: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -176,40 +169,209 @@ pub struct Rewards {
     pub min_size: rust_decimal::Decimal,
     pub rates: Option<serde_json::Value>,
 }
-#[derive(Debug, Clone, serde::Deserialize)]
-pub struct Balance {
-    pub asset: String,
-    pub balance: rust_decimal::Decimal,
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardRate {
+    #[serde(rename = "asset_address")]
+    pub asset_address: String,
+    #[serde(rename = "rewards_daily_rate")]
+    pub rewards_daily_rate: rust_decimal::Decimal,
+}
+
+impl Rewards {
+    /// Sum of daily reward rates across reward assets
+    pub fn daily_rate(&self) -> Decimal {
+        self.rates
+            .clone()
+            .and_then(|v| serde_json::from_value::<Vec<RewardRate>>(v).ok())
+            .map(|rates| rates.iter().map(|r| r.rewards_daily_rate).sum())
+            .unwrap_or(Decimal::ZERO)
+    }
+}
+
+/// Maker earnings for one market/day as reported by the CLOB
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardEarning {
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(rename = "condition_id")]
+    pub condition_id: String,
+    #[serde(rename = "asset_address", default)]
+    pub asset_address: Option<String>,
+    #[serde(rename = "maker_address", default)]
+    pub maker_address: Option<String>,
+    pub earnings: rust_decimal::Decimal,
+    #[serde(rename = "asset_rate", default)]
+    pub asset_rate: Option<rust_decimal::Decimal>,
 }
 
+/// One of our own resting orders
 #[derive(Debug, Clone)]
-pub struct OrderBookLevel {
+pub struct RestingOrder {
+    pub order_id: String,
+    pub token_id: String,
+    pub side: String, // "BUY" or "SELL"
     pub price: Decimal,
     pub size: Decimal,
 }
 
-/// Simulate how much it costs to buy `target_size` shares
-/// walking up the ask side of the book
-pub fn simulate_buy_cost(
-    asks: &[OrderBookLevel],
-    target_size: Decimal,
-) -> Option<Decimal> {
-    let mut remaining = target_size;
-    let mut cost = dec!(0);
+/// One of our live orders as reported by the CLOB
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub id: String,
+    #[serde(default)]
+    pub status: String,
+    /// condition_id
+    pub market: String,
+    #[serde(rename = "asset_id")]
+    pub asset_id: String,
+    pub side: String,
+    #[serde(rename = "original_size")]
+    pub original_size: Decimal,
+    #[serde(rename = "size_matched", default)]
+    pub size_matched: Decimal,
+    pub price: Decimal,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(rename = "order_type", default)]
+    pub order_type: String,
+}
 
-    for level in asks {
-        if remaining <= dec!(0) {
-            break;
-        }
+impl OpenOrder {
+    pub fn remaining(&self) -> Decimal {
+        (self.original_size - self.size_matched).max(Decimal::ZERO)
+    }
+}
+
+/// Maker side of a trade we took part in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MakerOrder {
+    #[serde(rename = "order_id")]
+    pub order_id: String,
+    #[serde(rename = "asset_id", default)]
+    pub asset_id: String,
+    #[serde(rename = "matched_amount")]
+    pub matched_amount: Decimal,
+    pub price: Decimal,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(default)]
+    pub side: String,
+    /// API key of the order's owner
+    #[serde(default)]
+    pub owner: String,
+}
+
+/// A match involving one of our orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub id: String,
+    #[serde(rename = "taker_order_id")]
+    pub taker_order_id: String,
+    /// condition_id
+    pub market: String,
+    #[serde(rename = "asset_id")]
+    pub asset_id: String,
+    pub side: String,
+    pub size: Decimal,
+    pub price: Decimal,
+    #[serde(rename = "fee_rate_bps", default)]
+    pub fee_rate_bps: Decimal,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(rename = "match_time", default)]
+    pub match_time: String,
+    /// "TAKER" or "MAKER" — which side of this match we were
+    #[serde(rename = "trader_side", default)]
+    pub trader_side: String,
+    #[serde(rename = "maker_orders", default)]
+    pub maker_orders: Vec<MakerOrder>,
+}
+
+impl Trade {
+    /// Whether one of `order_ids` took part in this trade
+    pub fn involves(&self, order_id: &str) -> bool {
+        self.taker_order_id == order_id
+            || self.maker_orders.iter().any(|m| m.order_id == order_id)
+    }
+}
 
-        let fill = remaining.min(level.size);
-        cost += fill * level.price;
-        remaining -= fill;
+/// A holding as reported by the data API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiPosition {
+    /// token_id
+    pub asset: String,
+    pub condition_id: String,
+    pub size: Decimal,
+    #[serde(default)]
+    pub avg_price: Decimal,
+    #[serde(default)]
+    pub cur_price: Decimal,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(default)]
+    pub outcome_index: u32,
+    #[serde(default)]
+    pub redeemable: bool,
+    #[serde(default)]
+    pub mergeable: bool,
+    #[serde(default)]
+    pub negative_risk: bool,
+}
+
+/// Which balance the CLOB balance-allowance endpoint reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetType {
+    /// USDC
+    Collateral,
+    /// One outcome token
+    Conditional(String),
+}
+
+/// How orders are signed; the CLOB scopes balances by it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureType {
+    Eoa,
+    #[default]
+    PolyProxy,
+    PolyGnosisSafe,
+}
+
+impl SignatureType {
+    pub fn code(self) -> u8 {
+        match self {
+            SignatureType::Eoa => 0,
+            SignatureType::PolyProxy => 1,
+            SignatureType::PolyGnosisSafe => 2,
+        }
     }
+}
+
+/// `/balance-allowance` as sent, in 6-decimal units
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawBalanceAllowance {
+    pub balance: String,
+    #[serde(default)]
+    pub allowance: Option<String>,
+    /// Newer responses: spender → allowance
+    #[serde(default)]
+    pub allowances: HashMap<String, String>,
+}
+
+/// Balance and the allowance the exchange can actually draw on, in shares / USDC
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BalanceAllowance {
+    pub balance: Decimal,
+    pub allowance: Decimal,
+}
 
-    if remaining > dec!(0) {
-        None // not enough liquidity
-    } else {
-        Some(cost)
+impl BalanceAllowance {
+    /// What an order can actually spend
+    pub fn available(&self) -> Decimal {
+        self.balance.min(self.allowance)
     }
 }
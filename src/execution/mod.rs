@@ -1,16 +1,32 @@
-use crate::client::{PolymarketClient, SignedOrderPayload};
-use crate::config::{TradingConfig, WalletConfig};
+use crate::account::AccountState;
+use crate::breaker::{CircuitBreaker, TripReason};
+use crate::capital::CapitalAllocator;
+use crate::client::{ClientError, ClientResult, PolymarketClient, SignedOrderPayload};
+use crate::config::{SizingPolicy, TradingConfig, WalletConfig};
+use crate::control::RuntimeControls;
 use crate::domain::*;
+use crate::latency::{Stage, Trace};
+use crate::metrics;
+use crate::portfolio::{Fill, Portfolio};
+use crate::recovery::{PersistedState, StateJournal};
+use crate::risk::{OrderIntent, RiskConfig, RiskManager, RiskRejection};
+use crate::wallet::ctf::CtfClient;
+use crate::wallet::nonce::NonceManager;
 use crate::wallet::signer::{ClobOrder, WalletSigner};
 
 use anyhow::{anyhow, Result};
-use log::info;
-use std::collections::HashMap;
+use futures_util::future::join_all;
+use log::{info, warn};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 
-use ethers::types::{H256, U256};
+use ethers::types::{Address, H256, U256};
 use ethers::utils::keccak256;
 
 // ==================================================
@@ -26,59 +42,167 @@ fn to_u256_scaled(value: &str) -> U256 {
     U256::from((v * 1_000_000.0) as u128)
 }
 
-// ==================================================
+/// Bundles to buy for `opportunity`: the sizing policy's answer, capped by
+/// the position size, risk limits, book depth and what the detector found
+fn bundle_size(
+    sizing: &SizingPolicy,
+    limits: &RiskConfig,
+    opportunity: &ArbitrageOpportunity,
+    max_position_size: f64,
+    available_usdc: Decimal,
+) -> f64 {
+    let eth_price = opportunity.eth_up_price.to_f64().unwrap_or(1.0);
+    let btc_price = opportunity.btc_down_price.to_f64().unwrap_or(1.0);
+    let bundle_cost = eth_price + btc_price;
+
+    if bundle_cost <= 0.0 {
+        return 0.0;
+    }
+
+    // Never size past what the exchange can actually draw on
+    let available = available_usdc.to_f64().unwrap_or(0.0);
+    let by_position = max_position_size.min(available) / bundle_cost;
 
-#[derive(Clone)]
-struct CachedMarketData {
-    market: MarketDetails,
-    cached_at: Instant,
+    // The Fixed policy spends up to `max_position_size`
+    let bundles = sizing
+        .bundles(eth_price, btc_price, available)
+        .unwrap_or(by_position);
+
+    // ---------------- caps ----------------
+    // `total_cost` covers every bundle the detector found room for
+    let by_detector = (opportunity.total_cost.to_f64().unwrap_or(0.0) / bundle_cost).round();
+    let by_outcome = limits.max_notional_per_outcome / eth_price.max(btc_price);
+    let by_window = limits.max_notional_per_window / bundle_cost;
+    let by_depth = opportunity
+        .depth
+        .map_or(f64::INFINITY, |d| d.to_f64().unwrap_or(0.0));
+
+    bundles
+        .min(by_position)
+        .min(by_detector)
+        .min(by_outcome)
+        .min(by_window)
+        .min(by_depth)
+        .max(0.0)
+        .floor()
 }
 
+/// Whether `trade` can be our taker fill of `order`, sent at `sent_unix`:
+/// same token and side, matched since the send, at a price the limit
+/// allows and for no more than the order's size
+fn is_taker_fill_of(trade: &Trade, order: &OrderRequest, sent_unix: u64) -> bool {
+    let (Ok(limit), Ok(size)) = (Decimal::from_str(&order.price), Decimal::from_str(&order.size))
+    else {
+        return false;
+    };
+
+    let buy = !order.side.eq_ignore_ascii_case("SELL");
+    let within_limit = if buy {
+        trade.price <= limit
+    } else {
+        trade.price >= limit
+    };
+    let since_send = trade
+        .match_time
+        .parse::<u64>()
+        .is_ok_and(|t| t >= sent_unix);
+
+    trade.trader_side.eq_ignore_ascii_case("TAKER")
+        && !trade.status.eq_ignore_ascii_case("FAILED")
+        && trade.asset_id == order.token_id
+        && trade.side.eq_ignore_ascii_case(&order.side)
+        && since_send
+        && within_limit
+        && trade.size <= size
+}
+
+/// Counts an order path as in flight until dropped
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// ==================================================
+
 pub struct Trader {
     api: Arc<PolymarketClient>,
     config: TradingConfig,
     wallet: WalletConfig,
     signer: Option<WalletSigner>,
+    risk: Arc<RiskManager>,
+    breaker: Arc<CircuitBreaker>,
+    account: Arc<AccountState>,
+    controls: Arc<RuntimeControls>,
+    capital: Arc<CapitalAllocator>,
+    nonces: Arc<NonceManager>,
+    // Journaled so a restart knows which positions are ours
+    portfolio: Arc<Portfolio>,
 
     total_profit: Arc<Mutex<f64>>,
     trades_executed: Arc<Mutex<u64>>,
+    estimated_fees: Arc<Mutex<f64>>,
     pending_trades: Arc<Mutex<HashMap<String, PendingTrade>>>,
-    market_cache: Arc<Mutex<HashMap<String, CachedMarketData>>>,
-    live_usdc_balance: Arc<Mutex<rust_decimal::Decimal>>,
+    flattened_markets: Arc<Mutex<HashSet<String>>>,
+
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+    // Set once the CLOB answers that batch submission isn't offered
+    batch_unavailable: AtomicBool,
+
+    journal: Option<StateJournal>,
 }
 
 impl Trader {
     // ==================================================
     // CONSTRUCTOR
     // ==================================================
+    /// `controls`, `capital`, `nonces` and `portfolio` must be the instances
+    /// shared with the control API, recovery and the emergency cancel path
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         api: Arc<PolymarketClient>,
         config: TradingConfig,
         wallet: WalletConfig,
         signer: Option<WalletSigner>,
+        risk: Arc<RiskManager>,
+        breaker: Arc<CircuitBreaker>,
+        account: Arc<AccountState>,
+        controls: Arc<RuntimeControls>,
+        capital: Arc<CapitalAllocator>,
+        nonces: Arc<NonceManager>,
+        portfolio: Arc<Portfolio>,
     ) -> Self {
         Self {
             api,
             config,
             wallet,
             signer,
+            risk,
+            breaker,
+            account,
+            controls,
+            capital,
+            nonces,
+            portfolio,
             total_profit: Arc::new(Mutex::new(0.0)),
             trades_executed: Arc::new(Mutex::new(0)),
+            estimated_fees: Arc::new(Mutex::new(0.0)),
             pending_trades: Arc::new(Mutex::new(HashMap::new())),
-            market_cache: Arc::new(Mutex::new(HashMap::new())),
-            live_usdc_balance: Arc::new(Mutex::new(rust_decimal::Decimal::ZERO)),
+            flattened_markets: Arc::new(Mutex::new(HashSet::new())),
+            shutting_down: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            batch_unavailable: AtomicBool::new(false),
+            journal: None,
         }
     }
 
-    // ==================================================
-    // BALANCE
-    // ==================================================
-    pub async fn refresh_balance(&self) -> Result<()> {
-        let balance = self.api.get_usdc_balance().await?;
-        *self.live_usdc_balance.lock().await = balance;
-
-        info!("💰 USDC balance updated: {}", balance);
-        Ok(())
+    /// Persist the order registry and open bundles after every change
+    pub fn with_journal(mut self, journal: StateJournal) -> Self {
+        self.journal = Some(journal);
+        self
     }
 
     // ==================================================
@@ -93,29 +217,76 @@ impl Trader {
             .as_ref()
             .ok_or_else(|| anyhow!("Wallet signer missing"))?;
 
-        let eth_market = self.api.get_market(&opportunity.eth_condition_id).await?;
-        let btc_market = self.api.get_market(&opportunity.btc_condition_id).await?;
+        let Some(_in_flight) = self.enter() else {
+            return Ok(());
+        };
 
-        if !eth_market.accepting_orders || !btc_market.accepting_orders {
-            info!("⛔ Trade blocked — market closed");
+        if !self.breaker.allows_entries() || self.controls.is_paused("arbitrage") {
             return Ok(());
         }
 
-        self.refresh_balance().await?;
+        // Market status comes from the background account service
+        if !self.account.accepting_orders(&opportunity.eth_condition_id).await
+            || !self.account.accepting_orders(&opportunity.btc_condition_id).await
+        {
+            info!("⛔ Trade blocked — market closed");
+            return Ok(());
+        }
 
-        let position_size = self.calculate_position_size(opportunity);
+        let available_usdc = self.capital.available("arbitrage").await;
+        let position_size = self.calculate_position_size(opportunity, available_usdc);
         if position_size <= 0.0 {
             info!("⛔ Zero-size trade skipped");
             return Ok(());
         }
 
         let size_str = format!("{:.6}", position_size);
+        let size = Decimal::from_str(&size_str)?;
 
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
+        // ================= RISK =================
+        let bundle_id = format!("arb-{}-{}", opportunity.eth_up_token_id, self.nonces.salt());
+        let legs = [
+            OrderIntent {
+                strategy: "arbitrage",
+                condition_id: opportunity.eth_condition_id.clone(),
+                token_id: opportunity.eth_up_token_id.clone(),
+                outcome: opportunity.eth_outcome.into(),
+                side: "BUY".into(),
+                price: opportunity.eth_up_price,
+                size,
+                bundle_id: Some(bundle_id.clone()),
+            },
+            OrderIntent {
+                strategy: "arbitrage",
+                condition_id: opportunity.btc_condition_id.clone(),
+                token_id: opportunity.btc_down_token_id.clone(),
+                outcome: opportunity.btc_outcome.into(),
+                side: "BUY".into(),
+                price: opportunity.btc_down_price,
+                size,
+                bundle_id: Some(bundle_id),
+            },
+        ];
+
+        if let Err(reason) = self.risk.try_reserve(&legs).await {
+            warn!("⛔ Risk rejected arbitrage: {}", reason);
+            self.on_risk_rejection(&reason);
+            return Ok(());
+        }
+
+        if let Err(reason) = self.capital.reserve(&legs).await {
+            warn!("⛔ Capital rejected arbitrage: {}", reason);
+            for leg in &legs {
+                self.risk.release(leg).await;
+            }
+            return Ok(());
+        }
+
         info!(
             "🚀 EXECUTING ARB | cost={} profit={}",
             opportunity.total_cost,
@@ -123,70 +294,736 @@ impl Trader {
         );
 
         // ================= ETH =================
-        let eth_sig = signer
-            .sign_order(&ClobOrder {
-                token_id: str_to_h256(&opportunity.eth_up_token_id),
-                side: 0,
-                price: to_u256_scaled(&opportunity.eth_up_price.to_string()),
-                size: to_u256_scaled(&size_str),
-                expiration: U256::from(now + 300),
-                nonce: U256::from(now),
+        let eth_payload = self
+            .sign_limit_order(
+                signer,
+                &legs[0].token_id,
+                "BUY",
+                legs[0].price,
+                &size_str,
+                "LIMIT",
+                now + 300,
+                self.order_nonce(&legs[0].condition_id).await,
+            )
+            .await;
+
+        // ================= BTC =================
+        let btc_payload = self
+            .sign_limit_order(
+                signer,
+                &legs[1].token_id,
+                "BUY",
+                legs[1].price,
+                &size_str,
+                "LIMIT",
+                now + 300,
+                self.order_nonce(&legs[1].condition_id).await,
+            )
+            .await;
+
+        let (eth_payload, btc_payload) = match (eth_payload, btc_payload) {
+            (Ok(eth), Ok(btc)) => (eth, btc),
+            (Err(e), _) | (_, Err(e)) => {
+                for leg in &legs {
+                    self.risk.release(leg).await;
+                    self.capital.release(leg).await;
+                }
+                return Err(e);
+            }
+        };
+
+        let mut trace = opportunity.trace.clone();
+        trace.mark(Stage::Signing);
+
+        metrics::global().opportunities_executed.inc();
+
+        // Both legs in one request when the CLOB allows it
+        let mut results = self
+            .submit_bundle(&[eth_payload, btc_payload], &trace)
+            .await
+            .into_iter();
+        let eth_result = results
+            .next()
+            .unwrap_or_else(|| Err(ClientError::Decode("no result for ETH leg".into())));
+        let btc_result = results
+            .next()
+            .unwrap_or_else(|| Err(ClientError::Decode("no result for BTC leg".into())));
+
+        if eth_result.is_ok() != btc_result.is_ok() {
+            self.breaker.trip(TripReason::LegImbalance {
+                detail: format!(
+                    "ETH leg {} / BTC leg {}",
+                    if eth_result.is_ok() { "sent" } else { "rejected" },
+                    if btc_result.is_ok() { "sent" } else { "rejected" },
+                ),
+            });
+        }
+
+        if eth_result.is_ok() && btc_result.is_ok() {
+            self.record_trade(&legs, size).await;
+        }
+
+        for (leg, result) in legs.into_iter().zip([eth_result, btc_result]) {
+            self.track_submission(leg, result, Some(Duration::from_secs(300)))
+                .await;
+        }
+        self.persist_state().await;
+
+        Ok(())
+    }
+
+    /// Send a bundle's legs in one batch request, or concurrently one by
+    /// one when the batch endpoint isn't available.
+    async fn submit_bundle(
+        &self,
+        payloads: &[SignedOrderPayload],
+        trace: &Trace,
+    ) -> Vec<ClientResult<OrderResponse>> {
+        // Trades matched from here on may be ours
+        let sent_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .saturating_sub(5);
+
+        let results = self.send_bundle(payloads, trace).await;
+
+        if results.iter().any(|r| r.as_ref().is_err_and(|e| e.may_have_landed())) {
+            return self.find_landed(payloads, results, sent_unix).await;
+        }
+        results
+    }
+
+    async fn send_bundle(
+        &self,
+        payloads: &[SignedOrderPayload],
+        trace: &Trace,
+    ) -> Vec<ClientResult<OrderResponse>> {
+        if !self.batch_unavailable.load(Ordering::SeqCst) {
+            let mut batch_trace = trace.clone();
+
+            match self.api.place_orders_batch(payloads, &mut batch_trace).await {
+                Ok(results) => {
+                    info!("⏱ Bundle latency (batch) {}", batch_trace);
+                    return results;
+                }
+                Err(e) if e.is_unsupported() => {
+                    warn!("Batch orders unavailable — sending legs one by one");
+                    self.batch_unavailable.store(true, Ordering::SeqCst);
+                }
+                // The request may have landed; resending could double the bundle
+                Err(e) => return payloads.iter().map(|_| Err(e.clone())).collect(),
+            }
+        }
+
+        let sends = payloads.iter().map(|payload| async move {
+            let mut leg_trace = trace.clone();
+            let result = self.api.place_signed_order(payload, &mut leg_trace).await;
+            (result, leg_trace)
+        });
+
+        let mut results = Vec::new();
+        for (i, (result, leg_trace)) in join_all(sends).await.into_iter().enumerate() {
+            info!("⏱ Bundle latency leg {} {}", i, leg_trace);
+            results.push(result);
+        }
+        results
+    }
+
+    /// After a failure that may have reached the book, look the legs up —
+    /// resting, or already matched as taker — so their reservations are
+    /// kept. If the exchange can't be asked, halt: the halt cancel-all
+    /// leaves nothing resting that we've stopped tracking.
+    async fn find_landed(
+        &self,
+        payloads: &[SignedOrderPayload],
+        results: Vec<ClientResult<OrderResponse>>,
+        sent_unix: u64,
+    ) -> Vec<ClientResult<OrderResponse>> {
+        let (open, trades) = match tokio::try_join!(
+            self.api.get_open_orders(),
+            self.api.get_trades(sent_unix)
+        ) {
+            Ok(found) => found,
+            Err(e) => {
+                self.breaker.trip(TripReason::Reconciliation {
+                    detail: format!("bundle outcome unknown after send failure: {}", e),
+                });
+                return results;
+            }
+        };
+
+        // Never claim an order we already track, or one twice
+        let mut claimed: HashSet<String> = self
+            .risk
+            .open_orders()
+            .await
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+
+        payloads
+            .iter()
+            .zip(results)
+            .map(|(payload, result)| {
+                let error = match result {
+                    Err(e) if e.may_have_landed() => e,
+                    other => return other,
+                };
+
+                let order = &payload.order;
+                let price = Decimal::from_str(&order.price).ok();
+                let size = Decimal::from_str(&order.size).ok();
+
+                let resting = open.iter().find(|o| {
+                    !claimed.contains(&o.id)
+                        && o.asset_id == order.token_id
+                        && o.side.eq_ignore_ascii_case(&order.side)
+                        && Some(o.price) == price
+                        && Some(o.original_size) == size
+                });
+                let matched = trades.iter().find(|t| {
+                    !claimed.contains(&t.taker_order_id) && is_taker_fill_of(t, order, sent_unix)
+                });
+
+                let (order_id, status) = match (resting, matched) {
+                    (Some(o), _) => (o.id.clone(), o.status.clone()),
+                    (None, Some(t)) => (t.taker_order_id.clone(), "matched".to_string()),
+                    (None, None) => return Err(error),
+                };
+
+                warn!("🔎 Order {} on {} landed despite: {}", order_id, order.token_id, error);
+                claimed.insert(order_id.clone());
+
+                Ok(OrderResponse {
+                    order_id: Some(order_id),
+                    status,
+                    message: Some(error.to_string()),
+                })
             })
-            .await?;
+            .collect()
+    }
 
-        let eth_payload = SignedOrderPayload {
-            order: OrderRequest {
-                token_id: opportunity.eth_up_token_id.clone(),
-                side: "BUY".into(),
-                size: size_str.clone(),
-                price: opportunity.eth_up_price.to_string(),
-                order_type: "LIMIT".into(),
-            },
-            signature: eth_sig.to_string(),
-            address: self.wallet.proxy_wallet.clone(),
+    /// Book a bundle whose legs were both accepted
+    async fn record_trade(&self, legs: &[OrderIntent; 2], size: Decimal) {
+        let bundle_cost = legs[0].price + legs[1].price;
+        let units = size.to_f64().unwrap_or_default();
+        let investment = (bundle_cost * size).to_f64().unwrap_or_default();
+        let profit = ((Decimal::ONE - bundle_cost) * size).to_f64().unwrap_or_default();
+
+        // Marketable legs pay the taker fee: rate * min(p, 1 - p) * size
+        let mut fees = Decimal::ZERO;
+        for leg in legs {
+            if let Some(market) = self.account.market(&leg.condition_id).await {
+                let rate = market.taker_base_fee / Decimal::from(10_000);
+                fees += rate * leg.price.min(Decimal::ONE - leg.price) * leg.size;
+            }
+        }
+
+        *self.trades_executed.lock().await += 1;
+        *self.total_profit.lock().await += profit;
+        *self.estimated_fees.lock().await += fees.to_f64().unwrap_or_default();
+
+        if let Some(bundle_id) = legs[0].bundle_id.clone() {
+            self.pending_trades.lock().await.insert(
+                bundle_id,
+                PendingTrade {
+                    eth_token_id: legs[0].token_id.clone(),
+                    btc_token_id: legs[1].token_id.clone(),
+                    eth_condition_id: legs[0].condition_id.clone(),
+                    btc_condition_id: legs[1].condition_id.clone(),
+                    investment_amount: investment,
+                    units,
+                    opened_unix: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                },
+            );
+        }
+    }
+
+    // ==================================================
+    // SINGLE ORDERS (QUOTING)
+    // ==================================================
+    pub async fn place_limit_order(
+        &self,
+        intent: &OrderIntent,
+        order_type: &str,
+    ) -> Result<OrderResponse> {
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("Wallet signer missing"))?;
+
+        let started = std::time::Instant::now();
+
+        let Some(_in_flight) = self.enter() else {
+            anyhow::bail!("Shutting down");
         };
 
-        // ================= BTC =================
-        let btc_sig = signer
+        if self.breaker.is_halted() {
+            anyhow::bail!("Trading halted");
+        }
+
+        // Degraded still lets sells work off inventory
+        if intent.is_buy() && !self.breaker.allows_entries() {
+            anyhow::bail!("Breaker degraded — no new entries");
+        }
+
+        if self.controls.is_paused(intent.strategy) {
+            anyhow::bail!("Strategy {} paused", intent.strategy);
+        }
+
+        if let Err(reason) = self.risk.try_reserve(std::slice::from_ref(intent)).await {
+            self.on_risk_rejection(&reason);
+            return Err(reason.into());
+        }
+
+        if let Err(reason) = self.capital.reserve(std::slice::from_ref(intent)).await {
+            self.risk.release(intent).await;
+            return Err(reason.into());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // GTC orders never expire on their own
+        let (expiration, ttl) = if order_type == "GTC" {
+            (0, None)
+        } else {
+            (now + 300, Some(Duration::from_secs(300)))
+        };
+
+        let payload = match self
+            .sign_limit_order(
+                signer,
+                &intent.token_id,
+                &intent.side,
+                intent.price,
+                &format!("{:.6}", intent.size),
+                order_type,
+                expiration,
+                self.order_nonce(&intent.condition_id).await,
+            )
+            .await
+        {
+            Ok(p) => p,
+            Err(e) => {
+                self.risk.release(intent).await;
+                self.capital.release(intent).await;
+                return Err(e);
+            }
+        };
+
+        let mut trace = Trace::start(started);
+        trace.mark(Stage::Signing);
+
+        let result = self.api.place_signed_order(&payload, &mut trace).await;
+        let response = result.as_ref().ok().cloned();
+
+        self.track_submission(intent.clone(), result, ttl).await;
+        self.persist_state().await;
+
+        response.ok_or_else(|| anyhow!("Order rejected"))
+    }
+
+    /// Hand the exchange's answer for one leg to risk and capital
+    async fn track_submission(
+        &self,
+        intent: OrderIntent,
+        result: ClientResult<OrderResponse>,
+        ttl: Option<Duration>,
+    ) {
+        self.breaker.record_order_result(result.is_ok());
+
+        let m = metrics::global();
+        if result.is_ok() {
+            m.orders_accepted.inc();
+        } else {
+            m.orders_rejected.inc();
+        }
+
+        match result {
+            Ok(OrderResponse {
+                order_id: Some(order_id),
+                ..
+            }) => {
+                self.capital.on_submitted(order_id.clone(), &intent, ttl).await;
+                self.risk.on_submitted(order_id, intent, ttl).await;
+            }
+            Ok(_) => {
+                self.risk.release(&intent).await;
+                self.capital.release(&intent).await;
+            }
+            Err(e) => {
+                warn!("Order for {} rejected: {}", intent.token_id, e);
+                self.risk.release(&intent).await;
+                self.capital.release(&intent).await;
+            }
+        }
+    }
+
+    fn on_risk_rejection(&self, reason: &RiskRejection) {
+        if let RiskRejection::DailyLoss { .. } = reason {
+            self.breaker.trip(TripReason::LossLimit {
+                detail: reason.to_string(),
+            });
+        }
+    }
+
+    // ==================================================
+    // STATE JOURNAL
+    // ==================================================
+    pub async fn persist_state(&self) {
+        let Some(journal) = self.journal.as_ref() else {
+            return;
+        };
+
+        if let Err(e) = journal.save(self.snapshot()).await {
+            warn!("Failed to persist trader state: {}", e);
+        }
+    }
+
+    async fn snapshot(&self) -> PersistedState {
+        PersistedState {
+            saved_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            orders: self
+                .risk
+                .open_orders()
+                .await
+                .into_iter()
+                .map(|(id, intent)| (id, intent.into()))
+                .collect(),
+            bundles: self.pending_trades.lock().await.clone(),
+            positions: self
+                .portfolio
+                .positions()
+                .await
+                .into_iter()
+                .map(|p| (p.token_id, p.size))
+                .collect(),
+        }
+    }
+
+    /// Bundles found still open at startup
+    pub async fn restore_bundles(&self, bundles: HashMap<String, PendingTrade>) {
+        *self.pending_trades.lock().await = bundles;
+    }
+
+    /// One of our orders matched: free its order slot and reservation,
+    /// and journal the position the portfolio booked for it
+    pub async fn on_fill(&self, fill: &Fill) {
+        self.risk.on_filled(&fill.order_id, fill.size).await;
+        self.capital.on_filled(&fill.order_id, fill.size).await;
+
+        self.persist_state().await;
+    }
+
+    // ==================================================
+    // SHUTDOWN
+    // ==================================================
+    /// Register an order path, unless we are shutting down
+    fn enter(&self) -> Option<InFlight<'_>> {
+        // Count first, then check, so `wait_idle` can't miss us
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(&self.in_flight);
+
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// False while the breaker is degraded or halted
+    pub fn allows_entries(&self) -> bool {
+        self.breaker.allows_entries()
+    }
+
+    /// Refuse every new order from now on
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    /// Wait for in-flight order requests; false on timeout
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = std::time::Instant::now() + timeout;
+
+        while self.in_flight.load(Ordering::SeqCst) > 0 {
+            if std::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        true
+    }
+
+    pub async fn session_summary(&self) {
+        let m = metrics::global();
+
+        info!("📊 SESSION SUMMARY");
+        info!("   Arbitrage trades : {}", *self.trades_executed.lock().await);
+        info!("   Expected profit  : {:.4} USDC", *self.total_profit.lock().await);
+        info!("   Realized PnL     : {:.4} USDC", m.realized_pnl.get());
+        info!("   Estimated fees   : {:.4} USDC", *self.estimated_fees.lock().await);
+        info!(
+            "   Orders           : {} accepted / {} rejected / {} cancelled / {} filled",
+            m.orders_accepted.get(),
+            m.orders_rejected.get(),
+            m.orders_cancelled.get(),
+            m.orders_filled.get()
+        );
+    }
+
+    /// Cancel every open order on the account
+    pub async fn cancel_all(&self) -> Result<()> {
+        self.api.cancel_all().await?;
+        self.risk.on_cancel_all().await;
+        self.capital.on_cancel_all().await;
+        self.persist_state().await;
+
+        info!("🧹 All open orders cancelled");
+        Ok(())
+    }
+
+    /// Void every signed order on-chain by bumping the exchange nonces.
+    /// Works even when the CLOB is unreachable.
+    pub async fn emergency_cancel(&self, ctf: &CtfClient, maker: Address) -> Result<()> {
+        let hashes = self.nonces.emergency_cancel(ctf, maker).await?;
+
+        self.risk.on_cancel_all().await;
+        self.capital.on_cancel_all().await;
+        self.persist_state().await;
+
+        warn!("🚨 On-chain cancel sent: {:?}", hashes);
+        Ok(())
+    }
+
+    pub async fn cancel_orders(&self, order_ids: &[String]) -> Result<()> {
+        if order_ids.is_empty() {
+            return Ok(());
+        }
+
+        self.api.cancel_orders(order_ids).await?;
+
+        for order_id in order_ids {
+            self.risk.on_cancelled(order_id).await;
+            self.capital.on_cancelled(order_id).await;
+        }
+        metrics::global().orders_cancelled.add(order_ids.len() as u64);
+        self.persist_state().await;
+        Ok(())
+    }
+
+    /// Cancel every order we have on one market
+    pub async fn cancel_market(&self, condition_id: &str) -> Result<()> {
+        self.api.cancel_market_orders(condition_id).await?;
+
+        self.risk.on_market_cancelled(condition_id).await;
+        self.capital.on_market_cancelled(condition_id).await;
+        self.persist_state().await;
+        Ok(())
+    }
+
+    /// Exchange nonce for orders on this market
+    async fn order_nonce(&self, condition_id: &str) -> u64 {
+        let neg_risk = self
+            .account
+            .market(condition_id)
+            .await
+            .map(|m| m.neg_risk)
+            .unwrap_or(false);
+
+        self.nonces.nonce(neg_risk)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn sign_limit_order(
+        &self,
+        signer: &WalletSigner,
+        token_id: &str,
+        side: &str,
+        price: Decimal,
+        size_str: &str,
+        order_type: &str,
+        expiration: u64,
+        nonce: u64,
+    ) -> Result<SignedOrderPayload> {
+        let salt = self.nonces.salt();
+
+        let signature = signer
             .sign_order(&ClobOrder {
-                token_id: str_to_h256(&opportunity.btc_down_token_id),
-                side: 0,
-                price: to_u256_scaled(&opportunity.btc_down_price.to_string()),
-                size: to_u256_scaled(&size_str),
-                expiration: U256::from(now + 300),
-                nonce: U256::from(now + 1),
+                salt,
+                token_id: str_to_h256(token_id),
+                side: if side == "SELL" { 1 } else { 0 },
+                price: to_u256_scaled(&price.to_string()),
+                size: to_u256_scaled(size_str),
+                expiration: U256::from(expiration),
+                nonce: U256::from(nonce),
             })
             .await?;
 
-        let btc_payload = SignedOrderPayload {
+        Ok(SignedOrderPayload {
             order: OrderRequest {
-                token_id: opportunity.btc_down_token_id.clone(),
-                side: "BUY".into(),
-                size: size_str,
-                price: opportunity.btc_down_price.to_string(),
-                order_type: "LIMIT".into(),
+                salt: salt.to_string(),
+                nonce: nonce.to_string(),
+                token_id: token_id.to_string(),
+                side: side.into(),
+                size: size_str.to_string(),
+                price: price.to_string(),
+                order_type: order_type.into(),
             },
-            signature: btc_sig.to_string(),
+            signature: signature.to_string(),
             address: self.wallet.proxy_wallet.clone(),
-        };
+        })
+    }
 
-        // ✅ SAFE async execution
-        let _ = tokio::join!(
-            self.api.place_signed_order(&eth_payload),
-            self.api.place_signed_order(&btc_payload),
-        );
+    // ==================================================
+    // PRE-CLOSE FLATTEN
+    // ==================================================
+    /// Cancel resting orders on markets about to resolve.
+    /// Runs once per market; later calls for the same condition are no-ops.
+    pub async fn flatten_before_close(&self, condition_ids: &[&str]) {
+        for condition_id in condition_ids {
+            if !self
+                .flattened_markets
+                .lock()
+                .await
+                .insert(condition_id.to_string())
+            {
+                continue;
+            }
 
-        Ok(())
+            info!("🧹 Pre-close flatten — cancelling orders on {}", condition_id);
+
+            if let Err(e) = self.cancel_market(condition_id).await {
+                warn!("Failed to cancel orders on {}: {}", condition_id, e);
+            }
+        }
+    }
+
+    fn calculate_position_size(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        available_usdc: Decimal,
+    ) -> f64 {
+        bundle_size(
+            &self.config.sizing,
+            self.risk.limits(),
+            opportunity,
+            self.controls.max_position_size(),
+            available_usdc,
+        )
     }
+}
 
-    fn calculate_position_size(&self, opportunity: &ArbitrageOpportunity) -> f64 {
-        let max_usd = self.config.max_position_size;
-        let cost = f64::try_from(opportunity.total_cost).unwrap_or(1.0);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::time::Instant;
+
+    /// 0.45 + 0.48 bundles, sized by the detector against `available` USDC
+    fn opportunity(available: Decimal, depth: Option<Decimal>) -> ArbitrageOpportunity {
+        let (eth, btc) = (dec!(0.45), dec!(0.48));
+        let by_capital = (available / (eth + btc)).floor();
+        let shares = depth.map_or(by_capital, |d| d.min(by_capital));
+
+        ArbitrageOpportunity {
+            eth_up_price: eth,
+            btc_down_price: btc,
+            total_cost: (eth + btc) * shares,
+            expected_profit: (Decimal::ONE - eth - btc) * shares,
+            depth,
+            eth_up_token_id: "eth-up".into(),
+            btc_down_token_id: "btc-down".into(),
+            eth_outcome: "Up",
+            btc_outcome: "Down",
+            eth_condition_id: "eth".into(),
+            btc_condition_id: "btc".into(),
+            trace: Trace::start(Instant::now()),
+        }
+    }
+
+    fn leg() -> OrderRequest {
+        OrderRequest {
+            salt: "1".into(),
+            nonce: "0".into(),
+            token_id: "eth-up".into(),
+            side: "BUY".into(),
+            size: "20.000000".into(),
+            price: "0.45".into(),
+            order_type: "LIMIT".into(),
+        }
+    }
 
-        if cost <= 0.0 {
-            return 0.0;
+    fn taker_trade(price: Decimal, size: Decimal, match_time: u64) -> Trade {
+        Trade {
+            id: "t1".into(),
+            taker_order_id: "0xabc".into(),
+            market: "eth".into(),
+            asset_id: "eth-up".into(),
+            side: "BUY".into(),
+            size,
+            price,
+            fee_rate_bps: Decimal::ZERO,
+            status: "MATCHED".into(),
+            outcome: "Up".into(),
+            match_time: match_time.to_string(),
+            trader_side: "TAKER".into(),
+            maker_orders: Vec::new(),
         }
+    }
+
+    #[test]
+    fn landed_leg_matches_its_own_fill() {
+        assert!(is_taker_fill_of(&taker_trade(dec!(0.44), dec!(20), 1_000), &leg(), 1_000));
+    }
+
+    #[test]
+    fn landed_leg_ignores_unrelated_fills() {
+        let order = leg();
+
+        // Same token and side, but before the send
+        assert!(!is_taker_fill_of(&taker_trade(dec!(0.45), dec!(20), 990), &order, 1_000));
+        // Through the order's limit
+        assert!(!is_taker_fill_of(&taker_trade(dec!(0.50), dec!(20), 1_000), &order, 1_000));
+        // Bigger than the order
+        assert!(!is_taker_fill_of(&taker_trade(dec!(0.45), dec!(35), 1_000), &order, 1_000));
+    }
+
+    #[test]
+    fn fixed_policy_spends_max_position_size() {
+        let available = dec!(1000);
+        let opp = opportunity(available, Some(dec!(500)));
+
+        let limits = RiskConfig::default();
+
+        let bundles = bundle_size(&SizingPolicy::Fixed, &limits, &opp, 50.0, available);
+
+        // $50 / $0.93 per bundle
+        assert_eq!(bundles, 53.0);
+    }
+
+    #[test]
+    fn fixed_policy_is_capped_by_detector_shares() {
+        let available = dec!(1000);
+        let mut opp = opportunity(available, None);
+        // Detector saw room for 20 bundles only
+        opp.total_cost = dec!(0.93) * dec!(20);
+        let limits = RiskConfig {
+            max_notional_per_outcome: 1e9,
+            max_notional_per_window: 1e9,
+            ..RiskConfig::default()
+        };
+
+        let bundles = bundle_size(&SizingPolicy::Fixed, &limits, &opp, 500.0, available);
 
-        (max_usd / cost).floor()
+        assert_eq!(bundles, 20.0);
     }
 }
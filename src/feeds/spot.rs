@@ -0,0 +1,316 @@
+use crate::metrics;
+use crate::model::fair_value::RealizedVol;
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::RwLock;
+use tokio::time::{sleep, Duration};
+use tokio_tungstenite::connect_async;
+use url::Url;
+
+const WINDOW_SECS: u64 = 900;
+
+/// A window's first trade later than this after the open doesn't
+/// stand in for the open price
+const OPEN_TOLERANCE_MS: u128 = 5_000;
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpotSource {
+    Binance,
+    Coinbase,
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpotFeedConfig {
+    pub source: SpotSource,
+
+    // Overrides the exchange's default endpoint
+    pub ws_url: Option<String>,
+
+    // Assets to follow, e.g. ["BTC", "ETH"]
+    pub assets: Vec<String>,
+
+    // Recorded raw messages, one per line (source = "replay")
+    pub replay_path: Option<PathBuf>,
+    // 1.0 = recorded pace, 0.0 = as fast as possible
+    #[serde(default)]
+    pub replay_speed: f64,
+
+    // Append every raw message here for later replay
+    pub record_path: Option<PathBuf>,
+}
+
+/* =======================
+SPOT STATE
+======================= */
+
+#[derive(Debug, Clone)]
+pub struct SpotMove {
+    pub window_start_unix: u64,
+    pub open_price: Decimal,
+    pub last_price: Decimal,
+    pub last_update_ms: u128,
+
+    // First trade came well after the window opened (started mid-window
+    // or reconnected late), so `open_price` isn't the real open
+    pub partial: bool,
+
+    // Carried across windows
+    pub vol: RealizedVol,
+}
+
+#[derive(Clone)]
+pub struct SpotCache {
+    inner: Arc<RwLock<HashMap<String, SpotMove>>>,
+}
+
+impl SpotCache {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Record a trade. The first trade seen in a new 15m window
+    /// becomes that window's opening price; the window is marked
+    /// `partial` when that trade came too long after the open.
+    pub async fn update(&self, asset: &str, price: Decimal, ts_ms: u128) {
+        let window_start = (ts_ms / 1000) as u64 / WINDOW_SECS * WINDOW_SECS;
+        let partial = ts_ms - window_start as u128 * 1000 > OPEN_TOLERANCE_MS;
+        let price_f64 = price.to_f64().unwrap_or(0.0);
+        let mut map = self.inner.write().await;
+
+        match map.get_mut(asset) {
+            Some(state) if state.window_start_unix == window_start => {
+                state.last_price = price;
+                state.last_update_ms = ts_ms;
+                state.vol.update(price_f64, ts_ms);
+            }
+            Some(state) if state.window_start_unix > window_start => {
+                // Out-of-order trade from a previous window
+            }
+            Some(state) => {
+                state.window_start_unix = window_start;
+                state.open_price = price;
+                state.last_price = price;
+                state.last_update_ms = ts_ms;
+                state.partial = partial;
+                state.vol.update(price_f64, ts_ms);
+            }
+            None => {
+                map.insert(
+                    asset.to_string(),
+                    SpotMove {
+                        window_start_unix: window_start,
+                        open_price: price,
+                        last_price: price,
+                        last_update_ms: ts_ms,
+                        partial,
+                        vol: RealizedVol::new(price_f64, ts_ms),
+                    },
+                );
+            }
+        }
+    }
+
+    pub async fn get(&self, asset: &str) -> Option<SpotMove> {
+        self.inner.read().await.get(asset).cloned()
+    }
+}
+
+/* =======================
+MESSAGE PARSING
+======================= */
+
+struct SpotTrade {
+    asset: String,
+    price: Decimal,
+    ts_ms: u128,
+}
+
+/// Binance `<symbol>@trade`: {"e":"trade","s":"BTCUSDT","p":"...","T":ms}
+fn parse_binance(json: &Value) -> Option<SpotTrade> {
+    if json.get("e")?.as_str()? != "trade" {
+        return None;
+    }
+
+    let symbol = json.get("s")?.as_str()?;
+    let asset = symbol
+        .strip_suffix("USDT")
+        .or_else(|| symbol.strip_suffix("USDC"))
+        .or_else(|| symbol.strip_suffix("USD"))?;
+
+    Some(SpotTrade {
+        asset: asset.to_uppercase(),
+        price: json.get("p")?.as_str()?.parse().ok()?,
+        ts_ms: json.get("T")?.as_u64()? as u128,
+    })
+}
+
+/// Coinbase `matches`: {"type":"match","product_id":"BTC-USD","price":"...","time":"RFC3339"}
+fn parse_coinbase(json: &Value) -> Option<SpotTrade> {
+    let kind = json.get("type")?.as_str()?;
+    if kind != "match" && kind != "last_match" {
+        return None;
+    }
+
+    let asset = json.get("product_id")?.as_str()?.split('-').next()?;
+    let time = json.get("time")?.as_str()?;
+    let ts = chrono::DateTime::parse_from_rfc3339(time).ok()?;
+
+    Some(SpotTrade {
+        asset: asset.to_uppercase(),
+        price: json.get("price")?.as_str()?.parse().ok()?,
+        ts_ms: ts.timestamp_millis() as u128,
+    })
+}
+
+fn parse_trade(text: &str) -> Option<SpotTrade> {
+    let json: Value = serde_json::from_str(text).ok()?;
+    // Combined-stream wrapper: {"stream": "...", "data": {...}}
+    let json = json.get("data").cloned().unwrap_or(json);
+
+    parse_binance(&json).or_else(|| parse_coinbase(&json))
+}
+
+/* =======================
+FEED
+======================= */
+
+pub async fn start_spot_feed(config: SpotFeedConfig, cache: SpotCache) {
+    match config.source {
+        SpotSource::Replay => {
+            if let Err(e) = replay(&config, &cache).await {
+                warn!("⚠️ Spot replay failed: {}", e);
+            }
+        }
+        _ => loop {
+            info!("🔌 Connecting to spot feed ({:?})", config.source);
+
+            if let Err(e) = connect_and_stream(&config, &cache).await {
+                warn!("⚠️ Spot WS error: {} — reconnecting in 2s", e);
+                sleep(Duration::from_secs(2)).await;
+            }
+        },
+    }
+}
+
+async fn connect_and_stream(
+    config: &SpotFeedConfig,
+    cache: &SpotCache,
+) -> anyhow::Result<()> {
+    let url = match (&config.ws_url, config.source) {
+        (Some(url), _) => url.clone(),
+        (None, SpotSource::Binance) => {
+            let streams: Vec<String> = config
+                .assets
+                .iter()
+                .map(|a| format!("{}usdt@trade", a.to_lowercase()))
+                .collect();
+            format!(
+                "wss://stream.binance.com:9443/stream?streams={}",
+                streams.join("/")
+            )
+        }
+        (None, _) => "wss://ws-feed.exchange.coinbase.com".to_string(),
+    };
+
+    let (ws, _) = connect_async(Url::parse(&url)?).await?;
+    let (mut write, mut read) = ws.split();
+
+    if config.source == SpotSource::Coinbase {
+        let products: Vec<String> = config
+            .assets
+            .iter()
+            .map(|a| format!("{}-USD", a.to_uppercase()))
+            .collect();
+
+        let sub = serde_json::json!({
+            "type": "subscribe",
+            "product_ids": products,
+            "channels": ["matches"]
+        });
+
+        write
+            .send(tokio_tungstenite::tungstenite::Message::Text(
+                sub.to_string(),
+            ))
+            .await?;
+    }
+
+    info!("📡 Subscribed to spot trades: {:?}", config.assets);
+
+    let mut recorder = match &config.record_path {
+        Some(path) => Some(
+            tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?,
+        ),
+        None => None,
+    };
+
+    while let Some(msg) = read.next().await {
+        if let tokio_tungstenite::tungstenite::Message::Text(text) = msg? {
+            if let Some(file) = recorder.as_mut() {
+                file.write_all(format!("{}\n", text).as_bytes()).await?;
+            }
+
+            metrics::global().spot_messages.inc();
+
+            if let Some(trade) = parse_trade(&text) {
+                cache.update(&trade.asset, trade.price, trade.ts_ms).await;
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!("Spot WebSocket disconnected"))
+}
+
+async fn replay(config: &SpotFeedConfig, cache: &SpotCache) -> anyhow::Result<()> {
+    let path = config
+        .replay_path
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("replay_path missing"))?;
+
+    info!("⏪ Replaying spot trades from {}", path.display());
+
+    let file = tokio::fs::File::open(path).await?;
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut last_ts: Option<u128> = None;
+    let mut count = 0u64;
+
+    while let Some(line) = lines.next_line().await? {
+        let Some(trade) = parse_trade(&line) else {
+            continue;
+        };
+
+        if config.replay_speed > 0.0 {
+            if let Some(prev) = last_ts {
+                let gap = trade.ts_ms.saturating_sub(prev) as f64;
+                sleep(Duration::from_millis((gap / config.replay_speed) as u64)).await;
+            }
+            last_ts = Some(trade.ts_ms);
+        }
+
+        cache.update(&trade.asset, trade.price, trade.ts_ms).await;
+        count += 1;
+    }
+
+    info!("⏪ Spot replay finished ({} trades)", count);
+    Ok(())
+}
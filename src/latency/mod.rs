@@ -0,0 +1,223 @@
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Histogram bucket upper bounds in microseconds (last bucket is +Inf)
+pub const BUCKETS_US: [u64; 16] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000,
+];
+
+/* =======================
+STAGES
+======================= */
+
+/// Each stage measures the time since the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// WS frame received → book written to PriceCache
+    CacheUpdate,
+    /// Cache updated → snapshot built (includes coalescing wait)
+    Snapshot,
+    /// Snapshot → strategy decision
+    Detection,
+    /// Decision → orders signed
+    Signing,
+    /// Signed → HTTP request dispatched
+    HttpSend,
+    /// Dispatched → exchange response
+    ExchangeAck,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [
+        Stage::CacheUpdate,
+        Stage::Snapshot,
+        Stage::Detection,
+        Stage::Signing,
+        Stage::HttpSend,
+        Stage::ExchangeAck,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::CacheUpdate => "cache_update",
+            Stage::Snapshot => "snapshot",
+            Stage::Detection => "detection",
+            Stage::Signing => "signing",
+            Stage::HttpSend => "http_send",
+            Stage::ExchangeAck => "exchange_ack",
+        }
+    }
+}
+
+/* =======================
+HISTOGRAM
+======================= */
+
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// Per-bucket (non-cumulative) counts, one extra for +Inf
+    pub counts: [u64; BUCKETS_US.len() + 1],
+    pub count: u64,
+    pub sum_us: u64,
+    pub max_us: u64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, d: Duration) {
+        let us = d.as_micros() as u64;
+        let idx = BUCKETS_US
+            .iter()
+            .position(|b| us <= *b)
+            .unwrap_or(BUCKETS_US.len());
+
+        self.counts[idx] += 1;
+        self.count += 1;
+        self.sum_us += us;
+        self.max_us = self.max_us.max(us);
+    }
+
+    /// Bucket upper bound containing the given quantile
+    pub fn quantile_us(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let target = (self.count as f64 * q).ceil() as u64;
+        let mut seen = 0;
+
+        for (i, c) in self.counts.iter().enumerate() {
+            seen += c;
+            if seen >= target {
+                return BUCKETS_US.get(i).copied().unwrap_or(self.max_us);
+            }
+        }
+        self.max_us
+    }
+}
+
+/* =======================
+RECORDER
+======================= */
+
+#[derive(Default)]
+pub struct LatencyRecorder {
+    stages: Mutex<BTreeMap<Stage, Histogram>>,
+}
+
+static RECORDER: OnceLock<LatencyRecorder> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Process-wide recorder
+pub fn global() -> &'static LatencyRecorder {
+    RECORDER.get_or_init(LatencyRecorder::default)
+}
+
+impl LatencyRecorder {
+    pub fn observe(&self, stage: Stage, d: Duration) {
+        self.stages
+            .lock()
+            .unwrap()
+            .entry(stage)
+            .or_default()
+            .observe(d);
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<Stage, Histogram> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    /// Per-stage summary, logged on shutdown
+    pub fn report(&self) {
+        let stages = self.snapshot();
+
+        info!("⏱ LATENCY REPORT (µs, bucket upper bounds)");
+        info!(
+            "{:<14} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            "stage", "count", "mean", "p50", "p90", "p99", "max"
+        );
+
+        for stage in Stage::ALL {
+            let Some(h) = stages.get(&stage) else {
+                continue;
+            };
+
+            info!(
+                "{:<14} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                stage.name(),
+                h.count,
+                h.sum_us / h.count.max(1),
+                h.quantile_us(0.50),
+                h.quantile_us(0.90),
+                h.quantile_us(0.99),
+                h.max_us
+            );
+        }
+    }
+}
+
+/* =======================
+TRACE
+======================= */
+
+/// Timestamps for one pass through the pipeline, tagged with the
+/// correlation id of the opportunity it produced
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub id: u64,
+    pub origin: Instant,
+    last: Instant,
+    marks: Vec<(Stage, Duration)>,
+}
+
+impl Trace {
+    /// Start a trace at `origin` (usually the WS receive time)
+    pub fn start(origin: Instant) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            origin,
+            last: origin,
+            marks: Vec::new(),
+        }
+    }
+
+    /// Record the time since the previous mark as `stage`
+    pub fn mark(&mut self, stage: Stage) {
+        self.mark_at(stage, Instant::now());
+    }
+
+    pub fn mark_at(&mut self, stage: Stage, at: Instant) {
+        let d = at.saturating_duration_since(self.last);
+        self.last = at;
+        self.marks.push((stage, d));
+
+        global().observe(stage, d);
+        debug!("⏱ [{}] {} {}µs", self.id, stage.name(), d.as_micros());
+    }
+
+    /// Same trace, new correlation id (one per opportunity)
+    pub fn fork(&self) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            ..self.clone()
+        }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.last.saturating_duration_since(self.origin)
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.id)?;
+        for (stage, d) in &self.marks {
+            write!(f, " {}={}µs", stage.name(), d.as_micros())?;
+        }
+        write!(f, " total={}µs", self.total().as_micros())
+    }
+}
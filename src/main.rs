@@ -1,25 +1,50 @@
+mod account;
+mod breaker;
+mod capital;
 mod client;
 mod config;
+mod control;
 mod domain;
 mod execution;
+mod feeds;
+mod latency;
+mod metrics;
+mod model;
+mod recovery;
 mod monitor;
+mod portfolio;
+mod rewards;
+mod risk;
 mod strategy;
 mod ws;
 mod cache;
 mod wallet;
 
+use account::AccountState;
 use anyhow::Result;
+use breaker::{BreakerState, CircuitBreaker, TripReason};
+use capital::CapitalAllocator;
 use clap::Parser;
-use config::{Args, Config};
-use log::{info, warn};
+use config::{Args, Command, Config};
+use control::{ControlContext, RuntimeControls};
+use log::{error, info, warn};
+use model::fair_value::FairValueModel;
 use std::sync::Arc;
 
 use client::PolymarketClient;
+use domain::AssetType;
 use execution::Trader;
 use monitor::MarketMonitor;
+use portfolio::Portfolio;
+use rewards::RewardsTracker;
+use risk::RiskManager;
+use strategy::market_maker::MarketMaker;
 use strategy::ArbitrageDetector;
+use wallet::ctf::CtfClient;
+use wallet::nonce::NonceManager;
 use wallet::signer::WalletSigner;
 use cache::PriceCache;
+use feeds::spot::SpotCache;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -41,6 +66,7 @@ async fn main() -> Result<()> {
     let api = Arc::new(PolymarketClient::new(
         config.polymarket.gamma_api_url.clone(),
         config.polymarket.clob_api_url.clone(),
+        config.polymarket.data_api_url.clone(),
         config
             .polymarket
             .api_key
@@ -51,7 +77,8 @@ async fn main() -> Result<()> {
         std::env::var("POLY_API_PASSPHRASE")
             .expect("POLY_API_PASSPHRASE missing"),
         false,
-    ));
+    )
+    .with_signature_type(config.wallet.signature_type));
 
     // ==================================================
     // WALLET + BALANCE LOGGING
@@ -63,10 +90,10 @@ async fn main() -> Result<()> {
         info!("🧾 Signer wallet: {}", signer.address());
         info!("🧾 Proxy wallet: {}", config.wallet.proxy_wallet);
 
-        match api.get_usdc_balance().await {
-            Ok(balance) => info!(
-                "💰 USDC balance (API scope): {}",
-                balance
+        match api.get_balance_allowance(&AssetType::Collateral).await {
+            Ok(usdc) => info!(
+                "💰 USDC balance: {} (allowance {})",
+                usdc.balance, usdc.allowance
             ),
             Err(e) => warn!("Failed to fetch USDC balance: {}", e),
         }
@@ -77,6 +104,59 @@ async fn main() -> Result<()> {
         None
     };
 
+    // ==================================================
+    // ON-CHAIN CTF CALLS
+    // ==================================================
+    let ctf = match (&config.ctf, &signer) {
+        (Some(ctf_config), Some(signer)) => match CtfClient::new(ctf_config.clone(), signer) {
+            Ok(client) => Some(Arc::new(client)),
+            Err(e) => {
+                warn!("⚠️ CTF client unavailable: {}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    // ==================================================
+    // ALLOWANCES
+    // ==================================================
+    if let Some(signer) = &signer {
+        // Checking needs only an RPC, so fall back to the default CTF settings
+        let checker = match &ctf {
+            Some(ctf) => ctf.clone(),
+            None => Arc::new(CtfClient::new(config.ctf.clone().unwrap_or_default(), signer)?),
+        };
+
+        let report = match checker.holder(&config.wallet.proxy_wallet) {
+            Ok(owner) => checker.check_allowances(owner).await,
+            Err(e) => Err(e),
+        };
+
+        match report {
+            Ok(report) => {
+                report.log();
+
+                if args.command == Some(Command::SetupAllowances) {
+                    let hashes = checker.approve_missing(&report).await?;
+                    info!("🔏 Sent {} approval transactions", hashes.len());
+
+                    checker.check_allowances(report.owner).await?.log();
+                    return Ok(());
+                }
+            }
+            Err(e) => warn!("Allowance check failed: {}", e),
+        }
+    }
+
+    if args.command == Some(Command::SetupAllowances) {
+        anyhow::bail!("setup-allowances needs a wallet private key and a reachable RPC");
+    }
+
+    if let Some(ctf) = ctf.clone() {
+        tokio::spawn(ctf.run_auto_redeem(api.clone(), config.wallet.proxy_wallet.clone()));
+    }
+
     // ==================================================
     // MARKET DISCOVERY
     // ==================================================
@@ -85,23 +165,43 @@ async fn main() -> Result<()> {
     info!("ETH Market: {}", eth_market.slug);
     info!("BTC Market: {}", btc_market.slug);
 
+    // ==================================================
+    // ACCOUNT STATE (BACKGROUND REST)
+    // ==================================================
+    let account = Arc::new(AccountState::new(config.account.clone(), api.clone()));
+    account.track(&eth_market.condition_id).await;
+    account.track(&btc_market.condition_id).await;
+
+    tokio::spawn(account.clone().run());
+
     // ==================================================
     // PRICE CACHE + TOKEN IDS
     // ==================================================
     let price_cache = PriceCache::new();
     let mut token_ids = Vec::new();
 
-    for group in &eth_market.tokens {
-    for t in group {
+    for t in eth_market.tokens.iter().flatten() {
         token_ids.push(t.token_id.clone());
     }
-}
 
-for group in &btc_market.tokens {
-    for t in group {
+    for t in btc_market.tokens.iter().flatten() {
         token_ids.push(t.token_id.clone());
     }
-}
+
+    // ==================================================
+    // PORTFOLIO (FILLS + POSITIONS)
+    // ==================================================
+    let portfolio = Arc::new(Portfolio::new(
+        config.portfolio.clone(),
+        api.clone(),
+        price_cache.clone(),
+        config.wallet.proxy_wallet.clone(),
+    ));
+
+    if config.wallet.private_key.is_some() {
+        portfolio.reconcile().await;
+        tokio::spawn(portfolio.clone().run());
+    }
 
     // ==================================================
     // WEBSOCKET
@@ -109,9 +209,44 @@ for group in &btc_market.tokens {
     {
         let cache = price_cache.clone();
         let ws_url = config.polymarket.ws_url.clone();
+        let heartbeat = config.staleness.clone();
 
         tokio::spawn(async move {
-            ws::start_ws(ws_url, cache, token_ids).await;
+            ws::start_ws(ws_url, cache, token_ids, heartbeat).await;
+        });
+    }
+
+    // ==================================================
+    // CIRCUIT BREAKER
+    // ==================================================
+    let breaker = Arc::new(CircuitBreaker::new(config.breaker.clone()));
+
+    {
+        let breaker = breaker.clone();
+        let cache = price_cache.clone();
+
+        tokio::spawn(async move {
+            breaker.watch_ws(cache).await;
+        });
+    }
+
+    // ==================================================
+    // METRICS
+    // ==================================================
+    if let Some(metrics_config) = config.metrics.clone() {
+        tokio::spawn(metrics::serve(metrics_config, price_cache.clone()));
+    }
+
+    // ==================================================
+    // SPOT REFERENCE FEED
+    // ==================================================
+    let spot_cache = SpotCache::new();
+
+    if let Some(spot_config) = config.spot.clone() {
+        let cache = spot_cache.clone();
+
+        tokio::spawn(async move {
+            feeds::spot::start_spot_feed(spot_config, cache).await;
         });
     }
 
@@ -124,49 +259,415 @@ for group in &btc_market.tokens {
         btc_market,
         config.trading.check_interval_ms,
         price_cache.clone(),
-    ));
+        spot_cache.clone(),
+        breaker.clone(),
+    )
+    .with_staleness(config.staleness.clone()));
 
     // ==================================================
     // STRATEGY + TRADER
     // ==================================================
-    let detector = Arc::new(
-        ArbitrageDetector::new(config.trading.min_profit_threshold),
+    let controls = Arc::new(RuntimeControls::new(&config.trading));
+
+    let mut detector = ArbitrageDetector::new(
+        controls.clone(),
+        config.trading.expiry.clone(),
     );
 
+    let fair_value = match (&config.fair_value, &config.spot) {
+        (Some(fv_config), Some(_)) => Some(FairValueModel::new(fv_config.clone())),
+        (Some(_), None) => {
+            warn!("⚠️ fair_value configured without a spot feed — ignored");
+            None
+        }
+        _ => None,
+    };
+
+    if let Some(model) = fair_value.clone() {
+        detector = detector.with_fair_value(model);
+    }
+
+    let detector = Arc::new(detector);
+
+    let risk = Arc::new(RiskManager::new(config.risk.clone()));
+    let capital = Arc::new(CapitalAllocator::new(config.capital.clone(), account.clone()));
+
+    // Nonces come from chain when we can reach it; 0 otherwise
+    let nonces = Arc::new(NonceManager::new());
+    if let Some(ctf) = ctf.as_ref() {
+        let synced = match ctf.holder(&config.wallet.proxy_wallet) {
+            Ok(maker) => nonces.sync(ctf, maker).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = synced {
+            warn!("Exchange nonce sync failed: {}", e);
+        }
+    }
+
     let trader = Arc::new(Trader::new(
         api.clone(),
         config.trading.clone(),
         config.wallet.clone(),
         signer,
-    ));
+        risk.clone(),
+        breaker.clone(),
+        account.clone(),
+        controls.clone(),
+        capital.clone(),
+        nonces.clone(),
+        portfolio.clone(),
+    )
+    .with_journal(recovery::StateJournal::new(
+        config.recovery.state_file.clone(),
+    )));
+
+    // ==================================================
+    // STARTUP RECONCILIATION
+    // ==================================================
+    if config.wallet.private_key.is_some() {
+        let detail = match recovery::reconcile(
+            &config.recovery,
+            &api,
+            &trader,
+            &risk,
+            &capital,
+            &config.wallet.proxy_wallet,
+        )
+        .await
+        {
+            Ok(report) => {
+                report.log();
+                (!report.is_clean()).then(|| report.mismatches.join("; "))
+            }
+            Err(e) => Some(format!("failed: {}", e)),
+        };
+
+        if let Some(detail) = detail {
+            if config.recovery.halt_on_mismatch {
+                breaker.trip(TripReason::Reconciliation { detail });
+            } else {
+                warn!("⚠️ Trading despite reconciliation issues: {}", detail);
+            }
+        }
+    }
+
+    let market_maker = config.market_making.clone().map(|mm_config| {
+        info!("🏦 Market making enabled");
+        Arc::new(MarketMaker::new(
+            mm_config,
+            account.clone(),
+            price_cache.clone(),
+            fair_value.clone(),
+            portfolio.clone(),
+        ))
+    });
+
+    // Fills free order slots and book realized PnL
+    {
+        let mut fills = portfolio.subscribe();
+        let risk = risk.clone();
+        let trader = trader.clone();
+        let account = account.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let fill = match fills.recv().await {
+                    Ok(fill) => fill,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Missed {} fill notifications", n);
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+
+                trader.on_fill(&fill).await;
+                // Spent USDC must show up before the reservation is forgotten
+                account.refresh_balance().await;
+                if !fill.realized_pnl.is_zero() {
+                    risk.record_realized_pnl(fill.realized_pnl).await;
+                }
+            }
+        });
+    }
+
+    // Cancel everything whenever the breaker trips (or we start halted)
+    if config.wallet.private_key.is_some() {
+        let mut status = breaker.subscribe();
+        let trader = trader.clone();
+        let market_maker = market_maker.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if status.borrow_and_update().state == BreakerState::Halted {
+                    cancel_all_while_halted(&trader, market_maker.as_deref(), &status).await;
+                }
+                if status.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let rewards_tracker = config.rewards.clone().map(|rewards_config| {
+        Arc::new(RewardsTracker::new(
+            rewards_config,
+            api.clone(),
+            price_cache.clone(),
+        ))
+    });
+
+    // Score the book against reward programs off the snapshot path
+    if let Some(tracker) = rewards_tracker.clone() {
+        let market_maker = market_maker.clone();
+
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(tracker.sample_interval());
+
+            loop {
+                tick.tick().await;
+
+                let orders = match market_maker.as_ref() {
+                    Some(mm) => mm.resting_orders().await,
+                    None => Vec::new(),
+                };
+                tracker.sample(&orders).await;
+            }
+        });
+    }
+
+    // ==================================================
+    // OPERATOR CONTROL API
+    // ==================================================
+    if let Some(control_config) = config.control.clone() {
+        let ctx = ControlContext {
+            controls: controls.clone(),
+            breaker: breaker.clone(),
+            trader: trader.clone(),
+            risk: risk.clone(),
+            account: account.clone(),
+            price_cache: price_cache.clone(),
+            market_maker: market_maker.clone(),
+            portfolio: portfolio.clone(),
+            capital: capital.clone(),
+            ctf: ctf.clone(),
+            proxy_wallet: config.wallet.proxy_wallet.clone(),
+        };
+
+        tokio::spawn(control::serve(control_config, ctx));
+    }
 
     // ==================================================
     // MAIN LOOP
     // ==================================================
-    monitor
-        .start_monitoring({
+    let mut monitoring = {
+        let monitor = monitor.clone();
+        let on_snapshot = {
             let detector = detector.clone();
             let trader = trader.clone();
+            let market_maker = market_maker.clone();
+            let rewards_tracker = rewards_tracker.clone();
+            let controls = controls.clone();
+            let capital = capital.clone();
+            let merger = ctf
+                .clone()
+                .filter(|c| c.auto_merge())
+                .map(|c| (c, portfolio.clone(), account.clone(), risk.clone()));
+            let merged = Arc::new(tokio::sync::Mutex::new(std::collections::HashSet::new()));
 
             move |snapshot| {
                 let detector = detector.clone();
                 let trader = trader.clone();
+                let market_maker = market_maker.clone();
+                let rewards_tracker = rewards_tracker.clone();
+                let controls = controls.clone();
+                let capital = capital.clone();
+                let merger = merger.clone();
+                let merged = merged.clone();
 
                 async move {
+                    if let Some(mm) = market_maker.as_ref() {
+                        if controls.is_paused("market_maker") {
+                            mm.pull_all(&trader).await;
+                        } else {
+                            mm.on_snapshot(&snapshot, &trader).await;
+                        }
+                    }
+
+                    if let Some(tracker) = rewards_tracker.as_ref() {
+                        tracker.track_markets(&snapshot).await;
+                    }
+
+                    if detector.expiry_phase(&snapshot).requires_flatten() {
+                        trader
+                            .flatten_before_close(&[
+                                &snapshot.eth_market.condition_id,
+                                &snapshot.btc_market.condition_id,
+                            ])
+                            .await;
+
+                        // Free capital held in complete sets before resolution
+                        if let Some((ctf, portfolio, account, risk)) = merger {
+                            for cid in [
+                                snapshot.eth_market.condition_id.clone(),
+                                snapshot.btc_market.condition_id.clone(),
+                            ] {
+                                if !merged.lock().await.insert(cid.clone()) {
+                                    continue;
+                                }
+
+                                let (ctf, portfolio, account, risk) =
+                                    (ctf.clone(), portfolio.clone(), account.clone(), risk.clone());
+
+                                tokio::spawn(async move {
+                                    if let Err(e) = wallet::ctf::merge_complete_sets(
+                                        &ctf, &portfolio, &account, &risk, &cid,
+                                    )
+                                    .await
+                                    {
+                                        warn!("Auto-merge on {} failed: {}", cid, e);
+                                    }
+                                });
+                            }
+                        }
+                    }
+
+                    for leg in detector.detect_mispriced_legs(&snapshot) {
+                        info!(
+                            "🎯 Mispriced {} {} ({}) | {:?} book={} fair={} edge={}",
+                            leg.market_name,
+                            leg.outcome,
+                            leg.token_id,
+                            leg.side,
+                            leg.book_price,
+                            leg.fair_value,
+                            leg.edge
+                        );
+                    }
+
+                    let available_usdc = capital.available("arbitrage").await;
                     let opportunities =
-                        detector.detect_opportunities(&snapshot);
+                        detector.detect_opportunities(&snapshot, available_usdc);
+                    metrics::global()
+                        .opportunities_detected
+                        .add(opportunities.len() as u64);
 
                     for opportunity in opportunities {
                         let _ = trader.execute_arbitrage(&opportunity).await;
                     }
                 }
             }
-        })
-        .await;
+        };
+
+        tokio::spawn(async move { monitor.start_monitoring(on_snapshot).await })
+    };
+
+    tokio::select! {
+        _ = &mut monitoring => warn!("Monitor loop exited"),
+        _ = shutdown_signal() => info!("🛑 Shutdown requested"),
+    }
+
+    // ==================================================
+    // GRACEFUL SHUTDOWN
+    // ==================================================
+    let shutdown = &config.shutdown;
+
+    info!("🛑 Stopping new orders");
+    trader.begin_shutdown();
+
+    if !trader
+        .wait_idle(std::time::Duration::from_secs(shutdown.drain_timeout_secs))
+        .await
+    {
+        warn!(
+            "⚠️ In-flight orders still pending after {}s",
+            shutdown.drain_timeout_secs
+        );
+    }
+    monitoring.abort();
+
+    if shutdown.cancel_orders && config.wallet.private_key.is_some() {
+        match trader.cancel_all().await {
+            Ok(()) => {
+                if let Some(mm) = market_maker.as_ref() {
+                    mm.clear_quotes().await;
+                }
+            }
+            Err(e) => warn!("Cancel-all on shutdown failed: {}", e),
+        }
+    }
+
+    if let Some(path) = &shutdown.metrics_snapshot {
+        let text = metrics::global().render(&price_cache).await;
+        if let Err(e) = std::fs::write(path, text) {
+            warn!("Failed to write metrics snapshot to {}: {}", path.display(), e);
+        }
+    }
+
+    trader.persist_state().await;
+
+    latency::global().report();
+    trader.session_summary().await;
 
     Ok(())
 }
 
+/// Cancel-all with backoff until the exchange confirms or the breaker is
+/// resumed. Quotes are only forgotten once they are really gone.
+async fn cancel_all_while_halted(
+    trader: &Trader,
+    market_maker: Option<&MarketMaker>,
+    status: &tokio::sync::watch::Receiver<breaker::BreakerStatus>,
+) {
+    let mut delay = std::time::Duration::from_secs(1);
+
+    loop {
+        match trader.cancel_all().await {
+            Ok(()) => {
+                if let Some(mm) = market_maker {
+                    mm.clear_quotes().await;
+                }
+                return;
+            }
+            Err(e) => error!(
+                "🚨 Cancel-all after halt failed — orders may still rest, retrying in {:?}: {}",
+                delay, e
+            ),
+        }
+
+        tokio::time::sleep(delay).await;
+        if status.borrow().state != BreakerState::Halted {
+            return;
+        }
+        delay = (delay * 2).min(std::time::Duration::from_secs(30));
+    }
+}
+
+/// Resolves on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+            }
+            Err(e) => {
+                warn!("SIGTERM handler unavailable: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 // ==================================================
 // MARKET DISCOVERY
 // ==================================================
@@ -0,0 +1,225 @@
+use crate::cache::PriceCache;
+use crate::latency::{self, Stage, BUCKETS_US};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Metric names are part of the dashboard contract — never rename.
+const PREFIX: &str = "polybot";
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// e.g. "127.0.0.1:9464"
+    pub listen_addr: SocketAddr,
+}
+
+/* =======================
+PRIMITIVES
+======================= */
+
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// f64 gauge stored as bits
+#[derive(Default)]
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+    pub fn set(&self, v: f64) {
+        self.0.store(v.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn add(&self, delta: f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + delta).to_bits())
+            });
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/* =======================
+REGISTRY
+======================= */
+
+#[derive(Default)]
+pub struct Metrics {
+    pub opportunities_detected: Counter,
+    pub opportunities_executed: Counter,
+
+    pub orders_accepted: Counter,
+    pub orders_rejected: Counter,
+    pub orders_cancelled: Counter,
+    pub orders_filled: Counter,
+
+    pub realized_pnl: Gauge,
+    pub unrealized_pnl: Gauge,
+    pub usdc_balance: Gauge,
+
+    pub ws_reconnects: Counter,
+    pub ws_messages: Counter,
+    pub spot_messages: Counter,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Process-wide metrics
+pub fn global() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    pub fn fill_ratio(&self) -> f64 {
+        let accepted = self.orders_accepted.get();
+        if accepted == 0 {
+            return 0.0;
+        }
+        self.orders_filled.get() as f64 / accepted as f64
+    }
+
+    /// Prometheus text exposition format
+    pub async fn render(&self, cache: &PriceCache) -> String {
+        let mut out = String::new();
+
+        counter(&mut out, "opportunities_detected_total", "Arbitrage opportunities detected", self.opportunities_detected.get());
+        counter(&mut out, "opportunities_executed_total", "Arbitrage opportunities sent to the exchange", self.opportunities_executed.get());
+
+        let _ = writeln!(out, "# HELP {PREFIX}_orders_total Orders by final status");
+        let _ = writeln!(out, "# TYPE {PREFIX}_orders_total counter");
+        for (status, c) in [
+            ("accepted", &self.orders_accepted),
+            ("rejected", &self.orders_rejected),
+            ("cancelled", &self.orders_cancelled),
+            ("filled", &self.orders_filled),
+        ] {
+            let _ = writeln!(out, "{PREFIX}_orders_total{{status=\"{status}\"}} {}", c.get());
+        }
+
+        gauge(&mut out, "fill_ratio", "Filled / accepted orders", self.fill_ratio());
+        gauge(&mut out, "realized_pnl_usdc", "Realized PnL in USDC", self.realized_pnl.get());
+        gauge(&mut out, "unrealized_pnl_usdc", "Unrealized PnL in USDC", self.unrealized_pnl.get());
+        gauge(&mut out, "usdc_balance", "USDC balance", self.usdc_balance.get());
+
+        counter(&mut out, "ws_reconnects_total", "Market WS reconnects", self.ws_reconnects.get());
+        counter(&mut out, "ws_messages_total", "Market WS messages received", self.ws_messages.get());
+        counter(&mut out, "spot_messages_total", "Spot feed messages received", self.spot_messages.get());
+
+        // ---------------- book staleness ----------------
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+
+        let _ = writeln!(out, "# HELP {PREFIX}_book_age_seconds Seconds since the last book update");
+        let _ = writeln!(out, "# TYPE {PREFIX}_book_age_seconds gauge");
+        for (token_id, last_ms) in cache.update_times().await {
+            let age = now_ms.saturating_sub(last_ms) as f64 / 1000.0;
+            let _ = writeln!(out, "{PREFIX}_book_age_seconds{{token_id=\"{token_id}\"}} {age}");
+        }
+
+        // ---------------- latency ----------------
+        let _ = writeln!(out, "# HELP {PREFIX}_stage_latency_seconds Pipeline stage latency");
+        let _ = writeln!(out, "# TYPE {PREFIX}_stage_latency_seconds histogram");
+        let stages = latency::global().snapshot();
+        for stage in Stage::ALL {
+            let h = stages.get(&stage).cloned().unwrap_or_default();
+            let name = stage.name();
+            let mut cumulative = 0;
+
+            for (i, bound) in BUCKETS_US.iter().enumerate() {
+                cumulative += h.counts[i];
+                let le = *bound as f64 / 1_000_000.0;
+                let _ = writeln!(out, "{PREFIX}_stage_latency_seconds_bucket{{stage=\"{name}\",le=\"{le}\"}} {cumulative}");
+            }
+            let _ = writeln!(out, "{PREFIX}_stage_latency_seconds_bucket{{stage=\"{name}\",le=\"+Inf\"}} {}", h.count);
+            let _ = writeln!(out, "{PREFIX}_stage_latency_seconds_sum{{stage=\"{name}\"}} {}", h.sum_us as f64 / 1_000_000.0);
+            let _ = writeln!(out, "{PREFIX}_stage_latency_seconds_count{{stage=\"{name}\"}} {}", h.count);
+        }
+
+        out
+    }
+}
+
+fn counter(out: &mut String, name: &str, help: &str, v: u64) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} counter");
+    let _ = writeln!(out, "{PREFIX}_{name} {v}");
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, v: f64) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} gauge");
+    let _ = writeln!(out, "{PREFIX}_{name} {v}");
+}
+
+/* =======================
+HTTP ENDPOINT
+======================= */
+
+pub async fn serve(config: MetricsConfig, cache: PriceCache) {
+    let make_svc = make_service_fn(move |_| {
+        let cache = cache.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let cache = cache.clone();
+                async move { Ok::<_, Infallible>(handle(req, &cache).await) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&config.listen_addr) {
+        Ok(builder) => builder.serve(make_svc),
+        Err(e) => {
+            warn!("⚠️ Metrics endpoint failed to bind {}: {}", config.listen_addr, e);
+            return;
+        }
+    };
+
+    info!("📈 Metrics on http://{}/metrics", config.listen_addr);
+
+    if let Err(e) = server.await {
+        warn!("⚠️ Metrics server stopped: {}", e);
+    }
+}
+
+async fn handle(req: Request<Body>, cache: &PriceCache) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(global().render(cache).await))
+            .unwrap(),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap(),
+    }
+}
@@ -0,0 +1,237 @@
+use crate::domain::{MarketData, TokenPrice};
+use crate::feeds::spot::SpotMove;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FairValueConfig {
+    /// Annualized vol used until enough spot samples are seen (0.6 = 60%)
+    pub default_annual_vol: f64,
+
+    /// Samples required before trusting the realized estimate
+    pub min_vol_samples: u32,
+
+    /// Flag a leg when the book is this far through fair value
+    pub min_edge: f64,
+}
+
+impl Default for FairValueConfig {
+    fn default() -> Self {
+        Self {
+            default_annual_vol: 0.6,
+            min_vol_samples: 60,
+            min_edge: 0.03,
+        }
+    }
+}
+
+/* =======================
+REALIZED VOL
+======================= */
+
+/// EWMA of squared log returns, sampled at most once per second.
+#[derive(Debug, Clone)]
+pub struct RealizedVol {
+    variance_per_sec: f64,
+    samples: u32,
+    sample_price: f64,
+    sample_ms: u128,
+}
+
+impl RealizedVol {
+    const LAMBDA: f64 = 0.97;
+
+    pub fn new(price: f64, ts_ms: u128) -> Self {
+        Self {
+            variance_per_sec: 0.0,
+            samples: 0,
+            sample_price: price,
+            sample_ms: ts_ms,
+        }
+    }
+
+    pub fn update(&mut self, price: f64, ts_ms: u128) {
+        if ts_ms < self.sample_ms + 1000 || self.sample_price <= 0.0 || price <= 0.0 {
+            return;
+        }
+
+        let dt = (ts_ms - self.sample_ms) as f64 / 1000.0;
+        let r = (price / self.sample_price).ln();
+        let observed = r * r / dt;
+
+        self.variance_per_sec = if self.samples == 0 {
+            observed
+        } else {
+            Self::LAMBDA * self.variance_per_sec + (1.0 - Self::LAMBDA) * observed
+        };
+
+        self.samples = self.samples.saturating_add(1);
+        self.sample_price = price;
+        self.sample_ms = ts_ms;
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Volatility per sqrt(second)
+    pub fn sigma_per_sqrt_sec(&self) -> f64 {
+        self.variance_per_sec.sqrt()
+    }
+}
+
+/* =======================
+PRICING
+======================= */
+
+/// Standard normal CDF (Abramowitz & Stegun 7.1.26, |err| < 1.5e-7)
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Probability that a driftless lognormal price ends at or above `open`.
+pub fn prob_close_above(
+    open: f64,
+    current: f64,
+    seconds_remaining: f64,
+    sigma_per_sqrt_sec: f64,
+) -> f64 {
+    if open <= 0.0 || current <= 0.0 {
+        return 0.5;
+    }
+
+    let stdev = sigma_per_sqrt_sec * seconds_remaining.max(0.0).sqrt();
+
+    if stdev <= f64::EPSILON {
+        return if current >= open { 1.0 } else { 0.0 };
+    }
+
+    let d = ((current / open).ln() - 0.5 * stdev * stdev) / stdev;
+    normal_cdf(d)
+}
+
+#[derive(Debug, Clone)]
+pub struct FairValue {
+    pub up: Decimal,
+    pub down: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegSide {
+    /// Ask is below fair value
+    Buy,
+    /// Bid is above fair value
+    Sell,
+}
+
+#[derive(Debug, Clone)]
+pub struct MispricedLeg {
+    pub market_name: String,
+    pub outcome: String,
+    pub token_id: String,
+    pub side: LegSide,
+    pub book_price: Decimal,
+    pub fair_value: Decimal,
+    pub edge: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct FairValueModel {
+    config: FairValueConfig,
+}
+
+impl FairValueModel {
+    pub fn new(config: FairValueConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn sigma_for(&self, spot: &SpotMove) -> f64 {
+        if spot.vol.samples() >= self.config.min_vol_samples {
+            spot.vol.sigma_per_sqrt_sec()
+        } else {
+            self.config.default_annual_vol / SECONDS_PER_YEAR.sqrt()
+        }
+    }
+
+    pub fn fair_value(&self, spot: &SpotMove, seconds_to_expiry: i64) -> Option<FairValue> {
+        let sigma = self.sigma_for(spot);
+        let p = prob_close_above(
+            spot.open_price.to_f64()?,
+            spot.last_price.to_f64()?,
+            seconds_to_expiry as f64,
+            sigma,
+        );
+
+        let up = Decimal::from_f64(p)?.round_dp(4);
+
+        Some(FairValue {
+            up,
+            down: Decimal::ONE - up,
+        })
+    }
+
+    /// Compare both legs of a market against fair value
+    pub fn mispriced_legs(&self, market: &MarketData, fair: &FairValue) -> Vec<MispricedLeg> {
+        let mut legs = Vec::new();
+
+        if let Some(up) = market.up_token.as_ref() {
+            legs.extend(self.check_leg(&market.market_name, "Up", up, fair.up));
+        }
+
+        if let Some(down) = market.down_token.as_ref() {
+            legs.extend(self.check_leg(&market.market_name, "Down", down, fair.down));
+        }
+
+        legs
+    }
+
+    fn check_leg(
+        &self,
+        market_name: &str,
+        outcome: &str,
+        token: &TokenPrice,
+        fair_value: Decimal,
+    ) -> Option<MispricedLeg> {
+        let min_edge = Decimal::from_f64(self.config.min_edge)?;
+
+        let (side, book_price, edge) = match (token.ask, token.bid) {
+            (Some(ask), _) if fair_value - ask >= min_edge => {
+                (LegSide::Buy, ask, fair_value - ask)
+            }
+            (_, Some(bid)) if bid - fair_value >= min_edge => {
+                (LegSide::Sell, bid, bid - fair_value)
+            }
+            _ => return None,
+        };
+
+        Some(MispricedLeg {
+            market_name: market_name.to_string(),
+            outcome: outcome.to_string(),
+            token_id: token.token_id.clone(),
+            side,
+            book_price,
+            fair_value,
+            edge,
+        })
+    }
+}
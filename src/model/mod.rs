@@ -0,0 +1 @@
+pub mod fair_value;
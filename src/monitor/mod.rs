@@ -1,11 +1,15 @@
+use crate::breaker::CircuitBreaker;
 use crate::client::PolymarketClient;
+use crate::config::StalenessConfig;
 use crate::domain::*;
 use crate::cache::PriceCache;
+use crate::feeds::spot::{SpotCache, SpotMove};
+use crate::latency::{Stage, Trace};
 use anyhow::Result;
 use log::{info, warn};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::time::{sleep, Duration};
-use rust_decimal::Decimal;
 
 pub struct MarketMonitor {
     api: Arc<PolymarketClient>,
@@ -22,6 +26,9 @@ pub struct MarketMonitor {
     current_period_timestamp: Arc<tokio::sync::Mutex<u64>>,
 
     price_cache: PriceCache,
+    spot_cache: SpotCache,
+    breaker: Arc<CircuitBreaker>,
+    staleness: StalenessConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -30,18 +37,54 @@ pub struct MarketSnapshot {
     pub btc_market: MarketData,
     pub eth_market_meta: MarketMeta,
     pub btc_market_meta: MarketMeta,
-    pub timestamp: std::time::Instant,
-    
+    pub eth_freshness: Freshness,
+    pub btc_freshness: Freshness,
+    pub eth_spot: Option<SpotMove>,
+    pub btc_spot: Option<SpotMove>,
+    pub trace: Trace,
 }
 
 
 #[derive(Debug, Clone)]
 pub struct MarketMeta {
-    pub name: String,
-    pub slug: String,
     pub end_time_unix: u64,
 }
 
+/// Age of a market's books against the staleness policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Freshness {
+    Fresh,
+    Stale { age_ms: u128 },
+    Missing,
+}
+
+impl Freshness {
+    pub fn is_fresh(&self) -> bool {
+        matches!(self, Freshness::Fresh)
+    }
+}
+
+impl MarketMeta {
+    /// Seconds until the window closes (negative once it has closed)
+    pub fn seconds_to_expiry(&self) -> i64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        self.end_time_unix as i64 - now as i64
+    }
+}
+
+impl MarketSnapshot {
+    /// Seconds until the first of the two markets closes
+    pub fn seconds_to_expiry(&self) -> i64 {
+        self.eth_market_meta
+            .seconds_to_expiry()
+            .min(self.btc_market_meta.seconds_to_expiry())
+    }
+}
+
 impl MarketMonitor {
     pub fn new(
         api: Arc<PolymarketClient>,
@@ -49,6 +92,8 @@ impl MarketMonitor {
         btc_market: Market,
         check_interval_ms: u64,
         price_cache: PriceCache,
+        spot_cache: SpotCache,
+        breaker: Arc<CircuitBreaker>,
     ) -> Self {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -61,6 +106,9 @@ impl MarketMonitor {
             btc_market: Arc::new(tokio::sync::Mutex::new(btc_market)),
             check_interval: Duration::from_millis(check_interval_ms),
             price_cache,
+            spot_cache,
+            breaker,
+            staleness: StalenessConfig::default(),
 
             eth_up_token_id: Arc::new(tokio::sync::Mutex::new(None)),
             eth_down_token_id: Arc::new(tokio::sync::Mutex::new(None)),
@@ -72,9 +120,14 @@ impl MarketMonitor {
         }
     }
 
+    pub fn with_staleness(mut self, staleness: StalenessConfig) -> Self {
+        self.staleness = staleness;
+        self
+    }
+
     fn end_time_from_slug(slug: &str) -> u64 {
         slug.split('-')
-            .last()
+            .next_back()
             .and_then(|s| s.parse::<u64>().ok())
             .map(|start| start + 900)
             .unwrap_or(0)
@@ -119,14 +172,47 @@ impl MarketMonitor {
         Ok(())
     }
 
+    /// Event-driven loop: a snapshot is built whenever one of our books
+    /// changes, with bursts coalesced into a single snapshot. `check_interval`
+    /// is only a fallback tick so expiry rules still run on a quiet book.
     pub async fn start_monitoring<F, Fut>(&self, on_snapshot: F)
     where
         F: Fn(MarketSnapshot) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = ()> + Send + 'static,
     {
-        info!("📡 Market monitor running (safe auto-rotation)");
+        info!("📡 Market monitor running (event-driven, safe auto-rotation)");
+
+        let mut updates = self.price_cache.subscribe();
+        let mut fallback = tokio::time::interval(self.check_interval);
 
         loop {
+            // Oldest triggering update's trace, so coalescing wait is counted
+            let trace = tokio::select! {
+                _ = fallback.tick() => Trace::start(std::time::Instant::now()),
+                update = updates.recv() => match update {
+                    Ok(update) => {
+                        if !self.is_tracked(&update.token_id).await {
+                            continue;
+                        }
+                        update.trace
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        Trace::start(std::time::Instant::now())
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        sleep(self.check_interval).await;
+                        continue;
+                    }
+                },
+            };
+
+            // Coalesce whatever piled up while we were busy
+            while matches!(
+                updates.try_recv(),
+                Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_))
+            ) {}
+            fallback.reset();
+
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
@@ -141,12 +227,31 @@ impl MarketMonitor {
                 *self.last_market_refresh.lock().await = None;
             }
 
-            if let Ok(snapshot) = self.fetch_market_data().await {
+            // Strategies stay stopped while halted
+            if self.breaker.is_halted() {
+                continue;
+            }
+
+            if let Ok(mut snapshot) = self.fetch_market_data().await {
+                snapshot.trace = trace;
+                snapshot.trace.mark(Stage::Snapshot);
                 on_snapshot(snapshot).await;
             }
+        }
+    }
 
-            sleep(self.check_interval).await;
+    async fn is_tracked(&self, token_id: &str) -> bool {
+        for id in [
+            &self.eth_up_token_id,
+            &self.eth_down_token_id,
+            &self.btc_up_token_id,
+            &self.btc_down_token_id,
+        ] {
+            if id.lock().await.as_deref() == Some(token_id) {
+                return true;
+            }
         }
+        false
     }
 
     pub async fn fetch_market_data(&self) -> Result<MarketSnapshot> {
@@ -155,12 +260,6 @@ impl MarketMonitor {
         let eth = self.eth_market.lock().await.clone();
         let btc = self.btc_market.lock().await.clone();
 
-        let _usdc_balance = self
-    .api
-    .get_usdc_balance()
-    .await
-    .unwrap_or(Decimal::ZERO);
-
         let eth_up_id = self.eth_up_token_id.lock().await.clone();
         let eth_down_id = self.eth_down_token_id.lock().await.clone();
         let btc_up_id = self.btc_up_token_id.lock().await.clone();
@@ -180,19 +279,51 @@ impl MarketMonitor {
                 down_token: self.fetch_token_price(&btc_down_id).await,
             },
             eth_market_meta: MarketMeta {
-                name: eth.question.clone(),
-                slug: eth.slug.clone(),
                 end_time_unix: Self::end_time_from_slug(&eth.slug),
             },
             btc_market_meta: MarketMeta {
-                name: btc.question.clone(),
-                slug: btc.slug.clone(),
                 end_time_unix: Self::end_time_from_slug(&btc.slug),
             },
-            timestamp: std::time::Instant::now(),
+            eth_freshness: self.market_freshness(&eth_up_id, &eth_down_id).await,
+            btc_freshness: self.market_freshness(&btc_up_id, &btc_down_id).await,
+            eth_spot: self.spot_cache.get("ETH").await,
+            btc_spot: self.spot_cache.get("BTC").await,
+            trace: Trace::start(std::time::Instant::now()),
         })
     }
 
+    /// Worst freshness of a market's two books
+    async fn market_freshness(
+        &self,
+        up_id: &Option<String>,
+        down_id: &Option<String>,
+    ) -> Freshness {
+        let up = self.token_freshness(up_id).await;
+        let down = self.token_freshness(down_id).await;
+        up.max(down)
+    }
+
+    async fn token_freshness(&self, token_id: &Option<String>) -> Freshness {
+        let Some(id) = token_id.as_ref() else {
+            return Freshness::Missing;
+        };
+        let Some(cached) = self.price_cache.get(id).await else {
+            return Freshness::Missing;
+        };
+
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let age_ms = now_ms.saturating_sub(cached.last_update_ms);
+
+        if age_ms > self.staleness.max_age_ms(id) as u128 {
+            Freshness::Stale { age_ms }
+        } else {
+            Freshness::Fresh
+        }
+    }
+
     async fn fetch_token_price(&self, token_id: &Option<String>) -> Option<TokenPrice> {
         let id = token_id.as_ref()?;
         let cached = self.price_cache.get(id).await?;
@@ -201,7 +332,11 @@ impl MarketMonitor {
             token_id: id.clone(),
             bid: cached.bids.first().map(|(p, _)| *p),
             ask: cached.asks.first().map(|(p, _)| *p),
-
+            ask_size: cached
+                .asks
+                .first()
+                .filter(|_| cached.sizes_known)
+                .map(|(_, s)| *s),
         })
     }
 }
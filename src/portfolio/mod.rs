@@ -0,0 +1,423 @@
+use crate::cache::PriceCache;
+use crate::client::{ClientResult, PolymarketClient};
+use crate::domain::Trade;
+use crate::metrics;
+
+use log::{info, warn};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, RwLock};
+use tokio::time::{sleep, Duration};
+
+/// Buffered fill notifications before slow subscribers start lagging
+const FILL_CHANNEL_CAPACITY: usize = 256;
+
+/// Sizes closer than this are treated as equal when reconciling
+const SIZE_TOLERANCE: Decimal = dec!(0.01);
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PortfolioConfig {
+    /// Poll our trades this often
+    pub fill_poll_ms: u64,
+    /// Compare against the positions endpoint this often
+    pub reconcile_secs: u64,
+    /// The positions endpoint lags fills; tokens filled this recently
+    /// keep our own size
+    pub reconcile_grace_secs: u64,
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        Self {
+            fill_poll_ms: 2_000,
+            reconcile_secs: 60,
+            reconcile_grace_secs: 120,
+        }
+    }
+}
+
+/* =======================
+TYPES
+======================= */
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Position {
+    pub token_id: String,
+    pub condition_id: String,
+    pub outcome: String,
+    pub size: Decimal,
+    pub avg_cost: Decimal,
+}
+
+/// One of our orders matching, from our side of the trade
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub trade_id: String,
+    pub order_id: String,
+    pub token_id: String,
+    pub condition_id: String,
+    pub outcome: String,
+    pub side: String, // "BUY" or "SELL"
+    pub size: Decimal,
+    pub price: Decimal,
+    /// Realized on sells against avg_cost
+    pub realized_pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct UnrealizedPnl {
+    /// What we'd get hitting the bid now
+    pub to_bid: Decimal,
+    pub to_mid: Decimal,
+}
+
+/// Our fills in a trade; as maker only our own maker orders count.
+/// A FAILED trade never settled on chain, so it fills nothing.
+fn our_fills(trade: &Trade, api_key: &str) -> Vec<Fill> {
+    if trade.status.eq_ignore_ascii_case("FAILED") {
+        return Vec::new();
+    }
+
+    let fill = |order_id: &str, token: &str, outcome: &str, side: &str, size, price| Fill {
+        trade_id: trade.id.clone(),
+        order_id: order_id.to_string(),
+        token_id: token.to_string(),
+        condition_id: trade.market.clone(),
+        outcome: outcome.to_string(),
+        side: side.to_uppercase(),
+        size,
+        price,
+        realized_pnl: Decimal::ZERO,
+    };
+
+    if trade.trader_side.eq_ignore_ascii_case("MAKER") {
+        trade
+            .maker_orders
+            .iter()
+            .filter(|m| m.owner == api_key)
+            .map(|m| {
+                // Makers sit on the other side of the taker
+                let side = if !m.side.is_empty() {
+                    m.side.clone()
+                } else if trade.side.eq_ignore_ascii_case("BUY") {
+                    "SELL".into()
+                } else {
+                    "BUY".into()
+                };
+                fill(&m.order_id, &m.asset_id, &m.outcome, &side, m.matched_amount, m.price)
+            })
+            .collect()
+    } else {
+        vec![fill(
+            &trade.taker_order_id,
+            &trade.asset_id,
+            &trade.outcome,
+            &trade.side,
+            trade.size,
+            trade.price,
+        )]
+    }
+}
+
+/* =======================
+PORTFOLIO
+======================= */
+
+/// Live book of the conditional tokens we hold, built from our fills and
+/// periodically checked against Polymarket's positions endpoint.
+pub struct Portfolio {
+    config: PortfolioConfig,
+    api: Arc<PolymarketClient>,
+    price_cache: PriceCache,
+    wallet: String,
+
+    positions: RwLock<HashMap<String, Position>>,
+    // token id → last fill applied
+    last_fill: RwLock<HashMap<String, Instant>>,
+    // trade id → first seen, so overlapping polls don't double count
+    seen_trades: RwLock<HashMap<String, Instant>>,
+    fills: broadcast::Sender<Fill>,
+}
+
+impl Portfolio {
+    pub fn new(
+        config: PortfolioConfig,
+        api: Arc<PolymarketClient>,
+        price_cache: PriceCache,
+        wallet: String,
+    ) -> Self {
+        let (fills, _) = broadcast::channel(FILL_CHANNEL_CAPACITY);
+
+        Self {
+            config,
+            api,
+            price_cache,
+            wallet,
+            positions: RwLock::new(HashMap::new()),
+            last_fill: RwLock::new(HashMap::new()),
+            seen_trades: RwLock::new(HashMap::new()),
+            fills,
+        }
+    }
+
+    /// Notified after every fill is applied
+    pub fn subscribe(&self) -> broadcast::Receiver<Fill> {
+        self.fills.subscribe()
+    }
+
+    // ==================================================
+    // READS
+    // ==================================================
+    pub async fn positions(&self) -> Vec<Position> {
+        self.positions.read().await.values().cloned().collect()
+    }
+
+    pub async fn position(&self, token_id: &str) -> Option<Position> {
+        self.positions.read().await.get(token_id).cloned()
+    }
+
+    /// Shares held on both outcomes of a market (mergeable into USDC)
+    pub async fn complete_sets(&self, condition_id: &str) -> Decimal {
+        let positions = self.positions.read().await;
+        let legs: Vec<Decimal> = positions
+            .values()
+            .filter(|p| p.condition_id == condition_id)
+            .map(|p| p.size)
+            .collect();
+
+        match legs.as_slice() {
+            [a, b] => (*a).min(*b).floor(),
+            _ => Decimal::ZERO,
+        }
+    }
+
+    /// Book a merge: both legs shrink and each set pays 1 USDC.
+    /// Returns the realized PnL against the legs' average cost.
+    pub async fn apply_merge(&self, condition_id: &str, sets: Decimal) -> Decimal {
+        let mut positions = self.positions.write().await;
+        let mut cost = Decimal::ZERO;
+
+        for p in positions.values_mut().filter(|p| p.condition_id == condition_id) {
+            cost += p.avg_cost;
+            p.size = (p.size - sets).max(Decimal::ZERO);
+        }
+        positions.retain(|_, p| !p.size.is_zero());
+
+        (Decimal::ONE - cost) * sets
+    }
+
+    /// Open positions marked to the cached book
+    pub async fn unrealized_pnl(&self) -> UnrealizedPnl {
+        let positions = self.positions().await;
+        let mut pnl = UnrealizedPnl::default();
+
+        for p in positions {
+            let Some(book) = self.price_cache.get(&p.token_id).await else {
+                continue;
+            };
+            let bid = book.bids.first().map(|(price, _)| *price);
+            let ask = book.asks.first().map(|(price, _)| *price);
+
+            if let Some(bid) = bid {
+                pnl.to_bid += (bid - p.avg_cost) * p.size;
+            }
+            if let Some(mid) = match (bid, ask) {
+                (Some(b), Some(a)) => Some((b + a) / dec!(2)),
+                (b, a) => b.or(a),
+            } {
+                pnl.to_mid += (mid - p.avg_cost) * p.size;
+            }
+        }
+
+        pnl
+    }
+
+    // ==================================================
+    // FILLS
+    // ==================================================
+    /// Fold a fill into the book; returns the realized PnL on sells
+    pub async fn apply_fill(&self, fill: &Fill) -> Decimal {
+        self.last_fill
+            .write()
+            .await
+            .insert(fill.token_id.clone(), Instant::now());
+
+        let mut positions = self.positions.write().await;
+        let p = positions
+            .entry(fill.token_id.clone())
+            .or_insert_with(|| Position {
+                token_id: fill.token_id.clone(),
+                condition_id: fill.condition_id.clone(),
+                outcome: fill.outcome.clone(),
+                size: Decimal::ZERO,
+                avg_cost: Decimal::ZERO,
+            });
+
+        let mut realized = Decimal::ZERO;
+
+        if fill.side == "SELL" {
+            let closed = fill.size.min(p.size);
+            realized = (fill.price - p.avg_cost) * closed;
+            p.size -= closed;
+        } else {
+            let cost = p.avg_cost * p.size + fill.price * fill.size;
+            p.size += fill.size;
+            p.avg_cost = cost / p.size;
+        }
+
+        if p.size.is_zero() {
+            positions.remove(&fill.token_id);
+        }
+
+        realized
+    }
+
+    async fn poll_fills(&self, after_unix: u64) -> ClientResult<()> {
+        let trades = self.api.get_trades(after_unix).await?;
+
+        for trade in trades {
+            {
+                let mut seen = self.seen_trades.write().await;
+                if seen.contains_key(&trade.id) {
+                    continue;
+                }
+                seen.insert(trade.id.clone(), Instant::now());
+            }
+
+            for mut fill in our_fills(&trade, &self.api.api_key) {
+                fill.realized_pnl = self.apply_fill(&fill).await;
+
+                info!(
+                    "✅ Fill {} {} {} @ {} (order {}, trade {})",
+                    fill.side, fill.size, fill.token_id, fill.price, fill.order_id, fill.trade_id
+                );
+
+                // No subscribers is fine
+                let _ = self.fills.send(fill);
+            }
+        }
+
+        self.seen_trades
+            .write()
+            .await
+            .retain(|_, t| t.elapsed() < Duration::from_secs(3600));
+
+        Ok(())
+    }
+
+    // ==================================================
+    // RECONCILIATION
+    // ==================================================
+    /// Adopt the exchange's view where it disagrees with ours, except for
+    /// tokens filled within the grace period that it may not show yet
+    pub async fn reconcile(&self) {
+        let remote = match self.api.get_positions(&self.wallet).await {
+            Ok(p) => p,
+            Err(e) => {
+                warn!("Position reconcile failed: {}", e);
+                return;
+            }
+        };
+
+        let grace = Duration::from_secs(self.config.reconcile_grace_secs);
+        let recent: Vec<String> = {
+            let mut last_fill = self.last_fill.write().await;
+            last_fill.retain(|_, t| t.elapsed() < grace);
+            last_fill.keys().cloned().collect()
+        };
+
+        let mut positions = self.positions.write().await;
+        let mut remote_tokens = Vec::new();
+
+        for r in remote.into_iter().filter(|r| !r.redeemable && !r.size.is_zero()) {
+            remote_tokens.push(r.asset.clone());
+
+            match positions.get_mut(&r.asset) {
+                Some(local) if (local.size - r.size).abs() <= SIZE_TOLERANCE => {}
+                // Lagging: a fresh fill may not be in the exchange's numbers yet
+                _ if recent.contains(&r.asset) => {}
+                Some(local) => {
+                    warn!(
+                        "⚖️ Position {} drifted: local {} vs exchange {}",
+                        r.asset, local.size, r.size
+                    );
+                    local.size = r.size;
+                }
+                None => {
+                    info!("⚖️ Adopting position {} × {}", r.size, r.asset);
+                    positions.insert(
+                        r.asset.clone(),
+                        Position {
+                            token_id: r.asset,
+                            condition_id: r.condition_id,
+                            outcome: r.outcome,
+                            size: r.size,
+                            avg_cost: r.avg_price,
+                        },
+                    );
+                }
+            }
+        }
+
+        positions.retain(|token, p| {
+            let keep = remote_tokens.contains(token) || recent.contains(token);
+            if !keep {
+                warn!("⚖️ Dropping position {} × {} not held on exchange", p.size, token);
+            }
+            keep
+        });
+    }
+
+    // ==================================================
+    // BACKGROUND LOOP
+    // ==================================================
+    pub async fn run(self: Arc<Self>) {
+        info!("📒 Portfolio tracker running");
+
+        let poll_every = Duration::from_millis(self.config.fill_poll_ms);
+        let reconcile_every = Duration::from_secs(self.config.reconcile_secs);
+
+        let mut last_reconcile: Option<Instant> = None;
+        let mut last_poll_unix = now_unix();
+
+        loop {
+            if last_reconcile.map(|t| t.elapsed() >= reconcile_every).unwrap_or(true) {
+                self.reconcile().await;
+                last_reconcile = Some(Instant::now());
+            }
+
+            // Overlap polls a little; seen_trades drops the repeats.
+            // The cursor only moves once a poll succeeded, so an outage
+            // is caught up on rather than skipped.
+            let now = now_unix();
+            match self.poll_fills(last_poll_unix.saturating_sub(30)).await {
+                Ok(()) => last_poll_unix = now,
+                Err(e) => warn!("Fill poll failed: {}", e),
+            }
+
+            let pnl = self.unrealized_pnl().await;
+            metrics::global()
+                .unrealized_pnl
+                .set(pnl.to_bid.to_f64().unwrap_or_default());
+
+            sleep(poll_every).await;
+        }
+    }
+}
+
+fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
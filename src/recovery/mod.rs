@@ -0,0 +1,315 @@
+use crate::capital::CapitalAllocator;
+use crate::client::PolymarketClient;
+use crate::control::STRATEGIES;
+use crate::domain::{OpenOrder, PendingTrade};
+use crate::execution::Trader;
+use crate::risk::{OrderIntent, RiskManager};
+
+use anyhow::Result;
+use log::{info, warn};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoveryConfig {
+    /// Order registry and open bundles, rewritten after every change
+    pub state_file: PathBuf,
+
+    /// How far back to look for fills that happened while we were down
+    pub trade_lookback_secs: u64,
+
+    /// Trip the breaker when the exchange disagrees with our state
+    pub halt_on_mismatch: bool,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            state_file: PathBuf::from("state.json"),
+            trade_lookback_secs: 86_400,
+            halt_on_mismatch: true,
+        }
+    }
+}
+
+/* =======================
+JOURNAL
+======================= */
+
+/// `OrderIntent` as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedOrder {
+    pub strategy: String,
+    pub condition_id: String,
+    pub token_id: String,
+    pub outcome: String,
+    pub side: String,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub bundle_id: Option<String>,
+}
+
+impl From<OrderIntent> for PersistedOrder {
+    fn from(i: OrderIntent) -> Self {
+        Self {
+            strategy: i.strategy.to_string(),
+            condition_id: i.condition_id,
+            token_id: i.token_id,
+            outcome: i.outcome,
+            side: i.side,
+            price: i.price,
+            size: i.size,
+            bundle_id: i.bundle_id,
+        }
+    }
+}
+
+impl PersistedOrder {
+    pub fn to_intent(&self) -> OrderIntent {
+        // Strategy names are static; unknown ones come back as "recovered"
+        let strategy = STRATEGIES
+            .into_iter()
+            .find(|s| *s == self.strategy)
+            .unwrap_or("recovered");
+
+        OrderIntent {
+            strategy,
+            condition_id: self.condition_id.clone(),
+            token_id: self.token_id.clone(),
+            outcome: self.outcome.clone(),
+            side: self.side.clone(),
+            price: self.price,
+            size: self.size,
+            bundle_id: self.bundle_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersistedState {
+    pub saved_unix: u64,
+    /// order_id → intent
+    pub orders: HashMap<String, PersistedOrder>,
+    /// bundle_id → bundle
+    pub bundles: HashMap<String, PendingTrade>,
+    /// token_id → shares the portfolio booked from our own fills
+    #[serde(default)]
+    pub positions: HashMap<String, Decimal>,
+}
+
+/// What the trader knows, on disk
+pub struct StateJournal {
+    path: PathBuf,
+    // One save at a time: they share the temp file
+    saving: Mutex<()>,
+}
+
+impl StateJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            saving: Mutex::new(()),
+        }
+    }
+
+    pub async fn load(&self) -> Option<PersistedState> {
+        let text = tokio::fs::read_to_string(&self.path).await.ok()?;
+
+        match serde_json::from_str(&text) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!("Ignoring unreadable {}: {}", self.path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Write to a temp file and rename, so a crash never leaves half a file.
+    /// `state` is taken under the save lock, so an older snapshot never
+    /// lands after a newer one.
+    pub async fn save(&self, state: impl Future<Output = PersistedState>) -> Result<()> {
+        let _saving = self.saving.lock().await;
+        let state = state.await;
+
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(&state)?).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+/* =======================
+RECONCILIATION
+======================= */
+
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub restored_orders: usize,
+    pub filled_offline: usize,
+    pub closed_offline: usize,
+    pub open_bundles: usize,
+    pub settled_bundles: usize,
+    /// Exchange and local state disagree — trading should wait for an operator
+    pub mismatches: Vec<String>,
+}
+
+impl ReconcileReport {
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn log(&self) {
+        info!(
+            "🔁 Reconciled: {} orders restored, {} filled / {} closed while down, {} open bundles, {} settled",
+            self.restored_orders,
+            self.filled_offline,
+            self.closed_offline,
+            self.open_bundles,
+            self.settled_bundles
+        );
+
+        for m in &self.mismatches {
+            warn!("⚠️ Reconciliation mismatch: {}", m);
+        }
+    }
+}
+
+fn intent_from_exchange(order: &OpenOrder) -> OrderIntent {
+    OrderIntent {
+        strategy: "recovered",
+        condition_id: order.market.clone(),
+        token_id: order.asset_id.clone(),
+        outcome: order.outcome.clone(),
+        side: order.side.to_uppercase(),
+        price: order.price,
+        size: order.remaining(),
+        bundle_id: None,
+    }
+}
+
+/// Rebuild the order registry and open bundles from the journal and the
+/// exchange. Run before any strategy is allowed to trade.
+pub async fn reconcile(
+    config: &RecoveryConfig,
+    api: &PolymarketClient,
+    trader: &Trader,
+    risk: &RiskManager,
+    capital: &CapitalAllocator,
+    wallet: &str,
+) -> Result<ReconcileReport> {
+    let journal = StateJournal::new(config.state_file.clone());
+    let persisted = journal.load().await.unwrap_or_default();
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let since = now.saturating_sub(config.trade_lookback_secs);
+
+    let open_orders = api.get_open_orders().await?;
+    let trades = api.get_trades(since).await?;
+    let positions = api.get_positions(wallet).await?;
+
+    let mut report = ReconcileReport::default();
+
+    // ---------------- order registry ----------------
+    let live_ids: HashSet<&str> = open_orders.iter().map(|o| o.id.as_str()).collect();
+
+    for order in &open_orders {
+        let intent = match persisted.orders.get(&order.id) {
+            Some(saved) => OrderIntent {
+                size: order.remaining(),
+                ..saved.to_intent()
+            },
+            None => {
+                report.mismatches.push(format!(
+                    "unknown open order {} ({} {} @ {})",
+                    order.id,
+                    order.side,
+                    order.remaining(),
+                    order.price
+                ));
+                intent_from_exchange(order)
+            }
+        };
+
+        capital.restore_open(order.id.clone(), &intent).await;
+        risk.restore_open(order.id.clone(), intent).await;
+        report.restored_orders += 1;
+    }
+
+    for (order_id, saved) in &persisted.orders {
+        if live_ids.contains(order_id.as_str()) {
+            continue;
+        }
+
+        if trades.iter().any(|t| t.involves(order_id)) {
+            risk.restore_filled(&saved.to_intent()).await;
+            report.filled_offline += 1;
+        } else {
+            report.closed_offline += 1;
+        }
+    }
+
+    // ---------------- bundles ----------------
+    let held: HashMap<&str, Decimal> = positions
+        .iter()
+        .filter(|p| !p.redeemable)
+        .map(|p| (p.asset.as_str(), p.size))
+        .collect();
+    let held_size = |token: &str| held.get(token).copied().unwrap_or(Decimal::ZERO);
+
+    let mut bundles = HashMap::new();
+
+    for (bundle_id, bundle) in &persisted.bundles {
+        let eth = held_size(&bundle.eth_token_id);
+        let btc = held_size(&bundle.btc_token_id);
+
+        match (eth.is_zero(), btc.is_zero()) {
+            (true, true) => report.settled_bundles += 1,
+            (false, false) => {
+                bundles.insert(bundle_id.clone(), bundle.clone());
+            }
+            _ => {
+                report.mismatches.push(format!(
+                    "bundle {} holds only one leg (ETH {} / BTC {})",
+                    bundle_id, eth, btc
+                ));
+                bundles.insert(bundle_id.clone(), bundle.clone());
+            }
+        }
+    }
+    report.open_bundles = bundles.len();
+
+    // ---------------- positions ----------------
+    let known: HashSet<&str> = persisted
+        .bundles
+        .values()
+        .flat_map(|b| [b.eth_token_id.as_str(), b.btc_token_id.as_str()])
+        .chain(persisted.orders.values().map(|i| i.token_id.as_str()))
+        .chain(persisted.positions.keys().map(String::as_str))
+        .chain(open_orders.iter().map(|o| o.asset_id.as_str()))
+        .collect();
+
+    // The portfolio already holds the exchange's sizes; only flag strays
+    for (token, size) in &held {
+        if !size.is_zero() && !known.contains(token) {
+            report
+                .mismatches
+                .push(format!("untracked position {} × {}", size, token));
+        }
+    }
+
+    trader.restore_bundles(bundles).await;
+    trader.persist_state().await;
+
+    Ok(report)
+}
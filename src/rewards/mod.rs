@@ -0,0 +1,399 @@
+use crate::cache::{CachedOrderbook, PriceCache};
+use crate::client::PolymarketClient;
+use crate::domain::*;
+use crate::monitor::MarketSnapshot;
+
+use log::{debug, info, warn};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Single-sided liquidity is scaled down by this factor
+/// while the midpoint sits inside [0.10, 0.90]
+const SINGLE_SIDED_DIVISOR: Decimal = dec!(3);
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RewardsConfig {
+    /// How often to score the book (Polymarket samples once a minute)
+    pub sample_interval_secs: u64,
+
+    /// How often to pull reported earnings and compare
+    pub reconcile_interval_secs: u64,
+}
+
+impl Default for RewardsConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval_secs: 60,
+            reconcile_interval_secs: 3600,
+        }
+    }
+}
+
+/* =======================
+SCORING
+======================= */
+
+/// Eligibility of one order against a market's rewards program
+#[derive(Debug, Clone)]
+pub struct OrderEligibility {
+    pub order_id: String,
+    pub eligible: bool,
+    pub spread: Decimal,
+    pub score: Decimal,
+}
+
+/// Quadratic order score: ((v - s) / v)^2 * size, zero outside max spread
+pub fn order_score(max_spread: Decimal, spread: Decimal, size: Decimal) -> Decimal {
+    if max_spread <= Decimal::ZERO || spread >= max_spread {
+        return Decimal::ZERO;
+    }
+
+    let r = (max_spread - spread) / max_spread;
+    r * r * size
+}
+
+/// Two-sided score from the "buy Up" side and the "buy Down" side
+pub fn q_min(q_one: Decimal, q_two: Decimal, midpoint: Decimal) -> Decimal {
+    let both = q_one.min(q_two);
+
+    if midpoint >= dec!(0.10) && midpoint <= dec!(0.90) {
+        both.max((q_one / SINGLE_SIDED_DIVISOR).max(q_two / SINGLE_SIDED_DIVISOR))
+    } else {
+        both
+    }
+}
+
+fn midpoint(book: &CachedOrderbook) -> Option<Decimal> {
+    let bid = book.bids.first()?.0;
+    let ask = book.asks.first()?.0;
+    Some((bid + ask) / dec!(2))
+}
+
+/// Per-side scores for the "buy Up" (Up bids, Down asks) and
+/// "buy Down" (Down bids, Up asks) sides, all relative to the Up midpoint
+struct SideScores {
+    q_one: Decimal,
+    q_two: Decimal,
+}
+
+/// Score our orders on one market; Down prices are scored in Up terms
+fn score_orders(
+    orders: &[RestingOrder],
+    up_token: &str,
+    down_token: &str,
+    mid: Decimal,
+    params: &RewardParams,
+) -> Vec<OrderEligibility> {
+    orders
+        .iter()
+        .filter_map(|o| {
+            let price = if o.token_id == up_token {
+                o.price
+            } else if o.token_id == down_token {
+                Decimal::ONE - o.price
+            } else {
+                return None;
+            };
+
+            let spread = (price - mid).abs();
+            let eligible = o.size >= params.min_size && spread < params.max_spread;
+
+            Some(OrderEligibility {
+                order_id: o.order_id.clone(),
+                eligible,
+                spread,
+                score: if eligible {
+                    order_score(params.max_spread, spread, o.size)
+                } else {
+                    Decimal::ZERO
+                },
+            })
+        })
+        .collect()
+}
+
+fn score_levels(
+    levels: &[(Decimal, Decimal)],
+    mid: Decimal,
+    max_spread: Decimal,
+    min_size: Decimal,
+) -> Decimal {
+    levels
+        .iter()
+        .filter(|(_, size)| *size >= min_size)
+        .map(|(price, size)| order_score(max_spread, (*price - mid).abs(), *size))
+        .sum()
+}
+
+/* =======================
+TRACKER
+======================= */
+
+#[derive(Debug, Clone, Default)]
+pub struct MarketRewardEstimate {
+    pub condition_id: String,
+    pub daily_rate: Decimal,
+    pub samples: u64,
+    pub eligible_samples: u64,
+    pub last_share: Decimal,
+    pub accrued: Decimal,
+    pub reported: Option<Decimal>,
+    /// Share of the pool the exchange says we hold, 0..1
+    pub reported_share: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RewardParams {
+    max_spread: Decimal,
+    min_size: Decimal,
+    daily_rate: Decimal,
+}
+
+pub struct RewardsTracker {
+    config: RewardsConfig,
+    api: Arc<PolymarketClient>,
+    price_cache: PriceCache,
+
+    // Markets of the current window, refreshed from each snapshot
+    markets: Mutex<Vec<MarketData>>,
+    params: Mutex<HashMap<String, RewardParams>>,
+    estimates: Mutex<HashMap<String, MarketRewardEstimate>>,
+    last_reconcile: Mutex<Option<Instant>>,
+}
+
+impl RewardsTracker {
+    pub fn new(config: RewardsConfig, api: Arc<PolymarketClient>, price_cache: PriceCache) -> Self {
+        Self {
+            config,
+            api,
+            price_cache,
+            markets: Mutex::new(Vec::new()),
+            params: Mutex::new(HashMap::new()),
+            estimates: Mutex::new(HashMap::new()),
+            last_reconcile: Mutex::new(None),
+        }
+    }
+
+    /// Remember which markets to sample. Cheap enough for the snapshot path.
+    pub async fn track_markets(&self, snapshot: &MarketSnapshot) {
+        *self.markets.lock().await = vec![snapshot.eth_market.clone(), snapshot.btc_market.clone()];
+    }
+
+    pub fn sample_interval(&self) -> Duration {
+        Duration::from_secs(self.config.sample_interval_secs)
+    }
+
+    /// Sample the tracked markets, and reconcile against reported
+    /// earnings if that interval elapsed. Makes REST calls, so it runs
+    /// on its own task rather than the snapshot path.
+    pub async fn sample(&self, orders: &[RestingOrder]) {
+        let markets = self.markets.lock().await.clone();
+
+        for market in &markets {
+            self.sample_market(market, orders).await;
+        }
+
+        if Self::due(&self.last_reconcile, self.config.reconcile_interval_secs).await {
+            self.reconcile().await;
+        }
+    }
+
+    async fn sample_market(&self, market: &MarketData, orders: &[RestingOrder]) {
+        let (Some(up), Some(down)) = (market.up_token.as_ref(), market.down_token.as_ref()) else {
+            return;
+        };
+        let Some(params) = self.reward_params(&market.condition_id).await else {
+            return;
+        };
+        if params.max_spread <= Decimal::ZERO {
+            return;
+        }
+
+        let (Some(up_book), Some(down_book)) = (
+            self.price_cache.get(&up.token_id).await,
+            self.price_cache.get(&down.token_id).await,
+        ) else {
+            return;
+        };
+        // Placeholder sizes would make the book look empty and our share 100%
+        if !up_book.sizes_known || !down_book.sizes_known {
+            return;
+        }
+        let Some(mid) = midpoint(&up_book) else {
+            return;
+        };
+
+        // Whole book (ours included), Down prices mirrored into Up terms
+        let down_bids: Vec<_> = down_book.bids.iter().map(|(p, s)| (Decimal::ONE - *p, *s)).collect();
+        let down_asks: Vec<_> = down_book.asks.iter().map(|(p, s)| (Decimal::ONE - *p, *s)).collect();
+
+        let book = SideScores {
+            q_one: score_levels(&up_book.bids, mid, params.max_spread, params.min_size)
+                + score_levels(&down_asks, mid, params.max_spread, params.min_size),
+            q_two: score_levels(&up_book.asks, mid, params.max_spread, params.min_size)
+                + score_levels(&down_bids, mid, params.max_spread, params.min_size),
+        };
+
+        // Our orders on the same two sides
+        let mut ours = SideScores {
+            q_one: Decimal::ZERO,
+            q_two: Decimal::ZERO,
+        };
+        for o in orders.iter().filter(|o| o.size >= params.min_size) {
+            let buy = o.side != "SELL";
+            let (price, one_side) = if o.token_id == up.token_id {
+                (o.price, buy)
+            } else if o.token_id == down.token_id {
+                (Decimal::ONE - o.price, !buy)
+            } else {
+                continue;
+            };
+
+            let score = order_score(params.max_spread, (price - mid).abs(), o.size);
+            if one_side {
+                ours.q_one += score;
+            } else {
+                ours.q_two += score;
+            }
+        }
+
+        for e in score_orders(orders, &up.token_id, &down.token_id, mid, &params) {
+            if e.eligible {
+                debug!(
+                    "🎁 Order {} scores {} on {}",
+                    e.order_id,
+                    e.score.round_dp(4),
+                    market.condition_id
+                );
+            } else {
+                info!(
+                    "🎁 Order {} earns no rewards on {} (spread {})",
+                    e.order_id, market.condition_id, e.spread
+                );
+            }
+        }
+
+        let our_q = q_min(ours.q_one, ours.q_two, mid);
+        let total_q = q_min(book.q_one, book.q_two, mid).max(our_q);
+
+        let share = if total_q > Decimal::ZERO {
+            our_q / total_q
+        } else {
+            Decimal::ZERO
+        };
+
+        let interval = Decimal::from(self.config.sample_interval_secs);
+        let mut estimates = self.estimates.lock().await;
+        let e = estimates
+            .entry(market.condition_id.clone())
+            .or_insert_with(|| MarketRewardEstimate {
+                condition_id: market.condition_id.clone(),
+                ..Default::default()
+            });
+
+        e.daily_rate = params.daily_rate;
+        e.samples += 1;
+        if our_q > Decimal::ZERO {
+            e.eligible_samples += 1;
+        }
+        e.last_share = share;
+        e.accrued += share * params.daily_rate * interval / dec!(86400);
+    }
+
+    /// Compare estimates with the earnings endpoint for today
+    pub async fn reconcile(&self) {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+
+        let earnings = match self.api.get_reward_earnings(&today).await {
+            Ok(e) => e,
+            Err(e) => {
+                warn!("Failed to fetch reward earnings: {}", e);
+                return;
+            }
+        };
+
+        let mut reported: HashMap<String, Decimal> = HashMap::new();
+        for e in earnings {
+            *reported.entry(e.condition_id).or_insert(Decimal::ZERO) += e.earnings;
+        }
+
+        // Percentages are informational; a failure shouldn't hide earnings
+        let shares = match self.api.get_reward_percentages().await {
+            Ok(p) => p,
+            Err(e) => {
+                warn!("Failed to fetch reward percentages: {}", e);
+                HashMap::new()
+            }
+        };
+
+        let mut estimates = self.estimates.lock().await;
+        for e in estimates.values_mut() {
+            e.reported = reported.get(&e.condition_id).copied();
+            e.reported_share = shares.get(&e.condition_id).map(|pct| pct / dec!(100));
+
+            info!(
+                "🎁 Rewards {} | share={} reported_share={:?} est={} reported={:?} eligible={}/{}",
+                e.condition_id,
+                e.last_share.round_dp(4),
+                e.reported_share.map(|s| s.round_dp(4)),
+                e.accrued.round_dp(4),
+                e.reported,
+                e.eligible_samples,
+                e.samples
+            );
+        }
+    }
+
+    // ==================================================
+    // HELPERS
+    // ==================================================
+    async fn due(last: &Mutex<Option<Instant>>, interval_secs: u64) -> bool {
+        let mut last = last.lock().await;
+        let due = last
+            .map(|t| t.elapsed() >= Duration::from_secs(interval_secs))
+            .unwrap_or(true);
+
+        if due {
+            *last = Some(Instant::now());
+        }
+        due
+    }
+
+    async fn reward_params(&self, condition_id: &str) -> Option<RewardParams> {
+        if let Some(p) = self.params.lock().await.get(condition_id) {
+            return Some(*p);
+        }
+
+        let details = match self.api.get_market(condition_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                warn!("Failed to load rewards for {}: {}", condition_id, e);
+                return None;
+            }
+        };
+
+        let params = RewardParams {
+            // max_spread is quoted in cents
+            max_spread: details.rewards.max_spread / dec!(100),
+            min_size: details.rewards.min_size,
+            daily_rate: details.rewards.daily_rate(),
+        };
+
+        self.params
+            .lock()
+            .await
+            .insert(condition_id.to_string(), params);
+
+        Some(params)
+    }
+}
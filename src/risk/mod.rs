@@ -0,0 +1,546 @@
+use crate::metrics;
+
+use chrono::{NaiveDate, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/* =======================
+CONFIG
+======================= */
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Max USDC committed across all markets in one 15m window
+    pub max_notional_per_window: f64,
+    /// Max USDC committed to one market (condition)
+    pub max_notional_per_market: f64,
+    /// Max USDC committed to one outcome token
+    pub max_notional_per_outcome: f64,
+    pub max_open_orders: usize,
+    /// Trading stops once realized PnL for the UTC day drops below -limit
+    pub max_daily_loss: f64,
+    pub max_orders_per_sec: usize,
+    /// Max |Up - Down| notional from single-leg orders, summed over markets
+    pub max_unhedged_exposure: f64,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_notional_per_window: 200.0,
+            max_notional_per_market: 100.0,
+            max_notional_per_outcome: 100.0,
+            max_open_orders: 20,
+            max_daily_loss: 50.0,
+            max_orders_per_sec: 5,
+            max_unhedged_exposure: 50.0,
+        }
+    }
+}
+
+/* =======================
+INTENTS
+======================= */
+
+/// An order a strategy wants to send, before signing
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderIntent {
+    pub strategy: &'static str,
+    pub condition_id: String,
+    pub token_id: String,
+    pub outcome: String, // "Up" or "Down"
+    pub side: String,    // "BUY" or "SELL"
+    pub price: Decimal,
+    pub size: Decimal,
+    // Legs sharing a bundle id hedge each other
+    pub bundle_id: Option<String>,
+}
+
+impl OrderIntent {
+    pub fn notional(&self) -> Decimal {
+        self.price * self.size
+    }
+
+    pub fn is_buy(&self) -> bool {
+        self.side != "SELL"
+    }
+}
+
+/* =======================
+REJECTIONS
+======================= */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskRejection {
+    WindowNotional { limit: Decimal, projected: Decimal },
+    MarketNotional { condition_id: String, limit: Decimal, projected: Decimal },
+    OutcomeNotional { token_id: String, limit: Decimal, projected: Decimal },
+    OpenOrders { limit: usize },
+    DailyLoss { limit: Decimal, realized: Decimal },
+    OrderRate { limit: usize },
+    UnhedgedExposure { limit: Decimal, projected: Decimal },
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WindowNotional { limit, projected } => {
+                write!(f, "window notional {} > {}", projected, limit)
+            }
+            Self::MarketNotional { condition_id, limit, projected } => {
+                write!(f, "market {} notional {} > {}", condition_id, projected, limit)
+            }
+            Self::OutcomeNotional { token_id, limit, projected } => {
+                write!(f, "outcome {} notional {} > {}", token_id, projected, limit)
+            }
+            Self::OpenOrders { limit } => write!(f, "open orders at limit {}", limit),
+            Self::DailyLoss { limit, realized } => {
+                write!(f, "daily loss {} exceeds {}", realized, limit)
+            }
+            Self::OrderRate { limit } => write!(f, "order rate above {}/s", limit),
+            Self::UnhedgedExposure { limit, projected } => {
+                write!(f, "unhedged exposure {} > {}", projected, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RiskRejection {}
+
+/* =======================
+STATE
+======================= */
+
+struct OpenOrder {
+    intent: OrderIntent,
+    filled: Decimal,
+    expires_at: Option<Instant>,
+}
+
+impl OpenOrder {
+    fn new(intent: OrderIntent, expires_at: Option<Instant>) -> Self {
+        Self {
+            intent,
+            filled: Decimal::ZERO,
+            expires_at,
+        }
+    }
+
+    /// The part still resting, whose exposure is freed on cancel
+    fn unfilled(&self) -> OrderIntent {
+        OrderIntent {
+            size: (self.intent.size - self.filled).max(Decimal::ZERO),
+            ..self.intent.clone()
+        }
+    }
+}
+
+#[derive(Default)]
+struct RiskState {
+    window_start: u64,
+    window_notional: Decimal,
+    market_notional: HashMap<String, Decimal>,
+    outcome_notional: HashMap<String, Decimal>,
+    // condition_id → (Up, Down) notional from single-leg orders
+    single_leg: HashMap<String, (Decimal, Decimal)>,
+
+    open_orders: HashMap<String, OpenOrder>,
+    recent_orders: VecDeque<Instant>,
+
+    pnl_day: Option<NaiveDate>,
+    realized_pnl: Decimal,
+}
+
+impl RiskState {
+    fn unhedged(&self) -> Decimal {
+        self.single_leg.values().map(|(up, down)| (*up - *down).abs()).sum()
+    }
+
+    fn apply(&mut self, intent: &OrderIntent, sign: Decimal) {
+        // Sells reduce inventory; only buys commit new capital
+        if !intent.is_buy() {
+            return;
+        }
+
+        let n = intent.notional() * sign;
+        self.window_notional = (self.window_notional + n).max(Decimal::ZERO);
+        *self.market_notional.entry(intent.condition_id.clone()).or_default() += n;
+        *self.outcome_notional.entry(intent.token_id.clone()).or_default() += n;
+
+        if intent.bundle_id.is_none() {
+            let leg = self.single_leg.entry(intent.condition_id.clone()).or_default();
+            if intent.outcome.eq_ignore_ascii_case("Up") {
+                leg.0 += n;
+            } else {
+                leg.1 += n;
+            }
+        }
+    }
+
+    /// Held exposure left the book (sold or merged)
+    fn close(&mut self, condition_id: &str, token_id: &str, outcome: &str, notional: Decimal) {
+        fn reduce(v: &mut Decimal, n: Decimal) {
+            *v = (*v - n).max(Decimal::ZERO);
+        }
+
+        if let Some(m) = self.market_notional.get_mut(condition_id) {
+            reduce(m, notional);
+        }
+        if let Some(o) = self.outcome_notional.get_mut(token_id) {
+            reduce(o, notional);
+        }
+        if let Some(leg) = self.single_leg.get_mut(condition_id) {
+            if outcome.eq_ignore_ascii_case("Up") {
+                reduce(&mut leg.0, notional);
+            } else {
+                reduce(&mut leg.1, notional);
+            }
+        }
+
+        self.market_notional.retain(|_, n| !n.is_zero());
+        self.outcome_notional.retain(|_, n| !n.is_zero());
+        self.single_leg.retain(|_, (up, down)| !up.is_zero() || !down.is_zero());
+    }
+}
+
+/* =======================
+MANAGER
+======================= */
+
+/// Pre-trade gate every order intent must pass
+pub struct RiskManager {
+    limits: RiskConfig,
+    state: Mutex<RiskState>,
+}
+
+fn dec(v: f64) -> Decimal {
+    Decimal::from_f64(v).unwrap_or(Decimal::ZERO)
+}
+
+impl RiskManager {
+    pub fn new(limits: RiskConfig) -> Self {
+        Self {
+            limits,
+            state: Mutex::new(RiskState::default()),
+        }
+    }
+
+    pub fn limits(&self) -> &RiskConfig {
+        &self.limits
+    }
+
+    /// Check all legs together and, if they pass, reserve their exposure.
+    /// Call `release` for any leg that is then not sent or is rejected.
+    pub async fn try_reserve(&self, intents: &[OrderIntent]) -> Result<(), RiskRejection> {
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+
+        Self::roll(&mut state);
+
+        // ---------------- daily loss ----------------
+        let loss_limit = dec(self.limits.max_daily_loss);
+        if -state.realized_pnl >= loss_limit {
+            return Err(RiskRejection::DailyLoss {
+                limit: loss_limit,
+                realized: state.realized_pnl,
+            });
+        }
+
+        // ---------------- rate ----------------
+        while let Some(t) = state.recent_orders.front() {
+            if now.duration_since(*t) >= Duration::from_secs(1) {
+                state.recent_orders.pop_front();
+            } else {
+                break;
+            }
+        }
+        if state.recent_orders.len() + intents.len() > self.limits.max_orders_per_sec {
+            return Err(RiskRejection::OrderRate {
+                limit: self.limits.max_orders_per_sec,
+            });
+        }
+
+        // ---------------- open orders ----------------
+        let expired: Vec<String> = state
+            .open_orders
+            .iter()
+            .filter(|(_, o)| o.expires_at.is_some_and(|t| t <= now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for order_id in expired {
+            if let Some(o) = state.open_orders.remove(&order_id) {
+                state.apply(&o.unfilled(), -Decimal::ONE);
+            }
+        }
+        if state.open_orders.len() + intents.len() > self.limits.max_open_orders {
+            return Err(RiskRejection::OpenOrders {
+                limit: self.limits.max_open_orders,
+            });
+        }
+
+        // ---------------- exposure ----------------
+        for intent in intents {
+            state.apply(intent, Decimal::ONE);
+        }
+
+        let result = self.check_exposure(&state, intents);
+
+        if result.is_err() {
+            for intent in intents {
+                state.apply(intent, -Decimal::ONE);
+            }
+        } else {
+            state.recent_orders.extend(intents.iter().map(|_| now));
+        }
+
+        result
+    }
+
+    fn check_exposure(&self, state: &RiskState, intents: &[OrderIntent]) -> Result<(), RiskRejection> {
+        let window_limit = dec(self.limits.max_notional_per_window);
+        if state.window_notional > window_limit {
+            return Err(RiskRejection::WindowNotional {
+                limit: window_limit,
+                projected: state.window_notional,
+            });
+        }
+
+        let market_limit = dec(self.limits.max_notional_per_market);
+        let outcome_limit = dec(self.limits.max_notional_per_outcome);
+
+        for intent in intents {
+            let market = state.market_notional.get(&intent.condition_id).copied().unwrap_or_default();
+            if market > market_limit {
+                return Err(RiskRejection::MarketNotional {
+                    condition_id: intent.condition_id.clone(),
+                    limit: market_limit,
+                    projected: market,
+                });
+            }
+
+            let outcome = state.outcome_notional.get(&intent.token_id).copied().unwrap_or_default();
+            if outcome > outcome_limit {
+                return Err(RiskRejection::OutcomeNotional {
+                    token_id: intent.token_id.clone(),
+                    limit: outcome_limit,
+                    projected: outcome,
+                });
+            }
+        }
+
+        let unhedged_limit = dec(self.limits.max_unhedged_exposure);
+        let unhedged = state.unhedged();
+        if unhedged > unhedged_limit {
+            return Err(RiskRejection::UnhedgedExposure {
+                limit: unhedged_limit,
+                projected: unhedged,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Undo a reservation for a leg that never reached the book
+    pub async fn release(&self, intent: &OrderIntent) {
+        self.state.lock().await.apply(intent, -Decimal::ONE);
+    }
+
+    /// The exchange accepted the order
+    pub async fn on_submitted(&self, order_id: String, intent: OrderIntent, ttl: Option<Duration>) {
+        self.state.lock().await.open_orders.insert(
+            order_id,
+            OpenOrder::new(intent, ttl.map(|d| Instant::now() + d)),
+        );
+    }
+
+    /// A resting order was cancelled — exposure of its unfilled part is freed
+    pub async fn on_cancelled(&self, order_id: &str) {
+        let mut state = self.state.lock().await;
+        if let Some(o) = state.open_orders.remove(order_id) {
+            state.apply(&o.unfilled(), -Decimal::ONE);
+        }
+    }
+
+    /// Every open order was cancelled at once
+    pub async fn on_cancel_all(&self) {
+        let mut state = self.state.lock().await;
+        let orders: Vec<OpenOrder> = state.open_orders.drain().map(|(_, o)| o).collect();
+
+        for o in orders {
+            state.apply(&o.unfilled(), -Decimal::ONE);
+        }
+    }
+
+    /// Every open order on one market was cancelled
+    pub async fn on_market_cancelled(&self, condition_id: &str) {
+        let mut state = self.state.lock().await;
+        let ids: Vec<String> = state
+            .open_orders
+            .iter()
+            .filter(|(_, o)| o.intent.condition_id == condition_id)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for order_id in ids {
+            if let Some(o) = state.open_orders.remove(&order_id) {
+                state.apply(&o.unfilled(), -Decimal::ONE);
+            }
+        }
+    }
+
+    /// Re-register an order found live on the exchange after a restart
+    pub async fn restore_open(&self, order_id: String, intent: OrderIntent) {
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+
+        if state.open_orders.contains_key(&order_id) {
+            return;
+        }
+        state.apply(&intent, Decimal::ONE);
+        state.open_orders.insert(order_id, OpenOrder::new(intent, None));
+    }
+
+    /// Re-apply exposure of an order that filled while we were down
+    pub async fn restore_filled(&self, intent: &OrderIntent) {
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+        state.apply(intent, Decimal::ONE);
+    }
+
+    /// `size` of a resting order filled. A buy's exposure stays; a sell
+    /// releases the exposure of what it sold. The order slot is freed once
+    /// it is completely filled.
+    pub async fn on_filled(&self, order_id: &str, size: Decimal) {
+        let mut state = self.state.lock().await;
+        let Some(o) = state.open_orders.get_mut(order_id) else {
+            return;
+        };
+
+        o.filled += size;
+        let intent = o.intent.clone();
+        if o.filled >= intent.size {
+            state.open_orders.remove(order_id);
+        }
+
+        if !intent.is_buy() {
+            let sold = intent.price * size;
+            state.close(&intent.condition_id, &intent.token_id, &intent.outcome, sold);
+        }
+        metrics::global().orders_filled.inc();
+    }
+
+    /// Shares of a position were merged back to USDC at `notional` cost
+    pub async fn on_merged(
+        &self,
+        condition_id: &str,
+        token_id: &str,
+        outcome: &str,
+        notional: Decimal,
+    ) {
+        self.state
+            .lock()
+            .await
+            .close(condition_id, token_id, outcome, notional);
+    }
+
+    pub async fn record_realized_pnl(&self, pnl: Decimal) {
+        let mut state = self.state.lock().await;
+        Self::roll(&mut state);
+        state.realized_pnl += pnl;
+
+        metrics::global()
+            .realized_pnl
+            .add(pnl.to_f64().unwrap_or_default());
+    }
+
+    pub async fn open_orders(&self) -> Vec<(String, OrderIntent)> {
+        self.state
+            .lock()
+            .await
+            .open_orders
+            .iter()
+            .map(|(id, o)| (id.clone(), o.intent.clone()))
+            .collect()
+    }
+
+    /// Reset window and daily counters when they roll over
+    fn roll(state: &mut RiskState) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let window = (now / 900) * 900;
+
+        if state.window_start != window {
+            state.window_start = window;
+            state.window_notional = Decimal::ZERO;
+
+            // Markets of closed windows resolve; keep only those still quoted
+            let (markets, tokens): (HashSet<String>, HashSet<String>) = state
+                .open_orders
+                .values()
+                .map(|o| (o.intent.condition_id.clone(), o.intent.token_id.clone()))
+                .unzip();
+            state.market_notional.retain(|id, _| markets.contains(id));
+            state.single_leg.retain(|id, _| markets.contains(id));
+            state.outcome_notional.retain(|id, _| tokens.contains(id));
+        }
+
+        let today = Utc::now().date_naive();
+        if state.pnl_day != Some(today) {
+            state.pnl_day = Some(today);
+            state.realized_pnl = Decimal::ZERO;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn intent(side: &str, size: Decimal) -> OrderIntent {
+        OrderIntent {
+            strategy: "market_maker",
+            condition_id: "eth".into(),
+            token_id: "eth-up".into(),
+            outcome: "Up".into(),
+            side: side.into(),
+            price: dec!(0.5),
+            size,
+            bundle_id: None,
+        }
+    }
+
+    async fn fill(risk: &RiskManager, order_id: &str, intent: OrderIntent) {
+        let size = intent.size;
+        risk.try_reserve(std::slice::from_ref(&intent)).await.unwrap();
+        risk.on_submitted(order_id.into(), intent, None).await;
+        risk.on_filled(order_id, size).await;
+    }
+
+    #[tokio::test]
+    async fn sell_fill_releases_exposure() {
+        let risk = RiskManager::new(RiskConfig::default());
+
+        // $50 of one-sided Up, right at the unhedged limit
+        fill(&risk, "buy", intent("BUY", dec!(100))).await;
+        assert!(matches!(
+            risk.try_reserve(&[intent("BUY", dec!(10))]).await,
+            Err(RiskRejection::UnhedgedExposure { .. })
+        ));
+
+        fill(&risk, "sell", intent("SELL", dec!(100))).await;
+
+        let state = risk.state.lock().await;
+        assert_eq!(state.unhedged(), Decimal::ZERO);
+        assert!(state.market_notional.is_empty());
+        assert!(state.outcome_notional.is_empty());
+    }
+}
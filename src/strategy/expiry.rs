@@ -0,0 +1,46 @@
+use crate::config::ExpiryRules;
+
+/// Where we are in the 15m window, as seen by a strategy's expiry rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpiryPhase {
+    /// Plenty of time left — early threshold applies
+    Early,
+    /// Normal trading
+    Open,
+    /// Too close to resolution for new entries
+    NoEntry,
+    /// Resting orders must be pulled
+    Flatten,
+    /// Window already over
+    Expired,
+}
+
+impl ExpiryPhase {
+    pub fn allows_entry(&self) -> bool {
+        matches!(self, ExpiryPhase::Early | ExpiryPhase::Open)
+    }
+
+    pub fn requires_flatten(&self) -> bool {
+        matches!(self, ExpiryPhase::Flatten | ExpiryPhase::Expired)
+    }
+}
+
+impl ExpiryRules {
+    pub fn phase(&self, seconds_to_expiry: i64) -> ExpiryPhase {
+        if seconds_to_expiry <= 0 {
+            return ExpiryPhase::Expired;
+        }
+
+        let secs = seconds_to_expiry as u64;
+
+        if secs <= self.flatten_secs {
+            ExpiryPhase::Flatten
+        } else if secs <= self.no_entry_secs {
+            ExpiryPhase::NoEntry
+        } else if secs > self.early_window_secs {
+            ExpiryPhase::Early
+        } else {
+            ExpiryPhase::Open
+        }
+    }
+}
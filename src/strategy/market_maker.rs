@@ -0,0 +1,496 @@
+use crate::account::AccountState;
+use crate::cache::PriceCache;
+use crate::config::MarketMakingConfig;
+use crate::domain::*;
+use crate::execution::Trader;
+use crate::feeds::spot::SpotMove;
+use crate::model::fair_value::FairValueModel;
+use crate::monitor::{MarketMeta, MarketSnapshot};
+use crate::portfolio::Portfolio;
+use crate::risk::OrderIntent;
+
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// Static per-market quoting parameters from the CLOB
+#[derive(Debug, Clone)]
+struct MarketParams {
+    tick: Decimal,
+    min_order_size: Decimal,
+    // Rewards max_spread converted from cents to price units
+    reward_max_spread: Decimal,
+    reward_min_size: Decimal,
+}
+
+#[derive(Debug, Clone)]
+struct LiveQuote {
+    order_id: String,
+    price: Decimal,
+    size: Decimal,
+}
+
+#[derive(Debug, Default)]
+struct TokenQuotes {
+    condition_id: String,
+    bid: Option<LiveQuote>,
+    ask: Option<LiveQuote>,
+    reference: Decimal,
+    book_update_ms: u128,
+    quoted_at: Option<Instant>,
+}
+
+/// One outcome of a market being quoted
+struct QuoteLeg<'a> {
+    condition_id: &'a str,
+    outcome: &'static str,
+    token: &'a TokenPrice,
+}
+
+/// Desired (price, size) on each side
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuotePair {
+    pub bid: Option<(Decimal, Decimal)>,
+    pub ask: Option<(Decimal, Decimal)>,
+}
+
+/// Passive quoting on both outcomes of each 15m market.
+/// Bids are posted up to `max_position`; asks only against inventory held.
+pub struct MarketMaker {
+    config: MarketMakingConfig,
+    account: Arc<AccountState>,
+    price_cache: PriceCache,
+    fair_value: Option<FairValueModel>,
+    // Inventory is whatever the portfolio holds
+    portfolio: Arc<Portfolio>,
+
+    params: Mutex<HashMap<String, MarketParams>>,
+    quotes: Mutex<HashMap<String, TokenQuotes>>,
+}
+
+impl MarketMaker {
+    pub fn new(
+        config: MarketMakingConfig,
+        account: Arc<AccountState>,
+        price_cache: PriceCache,
+        fair_value: Option<FairValueModel>,
+        portfolio: Arc<Portfolio>,
+    ) -> Self {
+        Self {
+            config,
+            account,
+            price_cache,
+            fair_value,
+            portfolio,
+            params: Mutex::new(HashMap::new()),
+            quotes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // ==================================================
+    // SNAPSHOT HANDLER
+    // ==================================================
+    pub async fn on_snapshot(&self, snapshot: &MarketSnapshot, trader: &Trader) {
+        for (market, meta, spot, freshness) in [
+            (&snapshot.eth_market, &snapshot.eth_market_meta, &snapshot.eth_spot, snapshot.eth_freshness),
+            (&snapshot.btc_market, &snapshot.btc_market_meta, &snapshot.btc_spot, snapshot.btc_freshness),
+        ] {
+            let phase = self.config.expiry.phase(meta.seconds_to_expiry());
+
+            if phase.requires_flatten() {
+                self.pull_market(market, trader).await;
+                continue;
+            }
+
+            // Never re-quote off a stale book or while the breaker is
+            // degraded; resting quotes are left as-is
+            if !phase.allows_entry() || !freshness.is_fresh() || !trader.allows_entries() {
+                continue;
+            }
+
+            let Some(params) = self.market_params(&market.condition_id).await else {
+                continue;
+            };
+
+            let fair = self.fair_up_price(spot.as_ref(), meta);
+
+            for (outcome, token, fair) in [
+                ("Up", market.up_token.as_ref(), fair),
+                ("Down", market.down_token.as_ref(), fair.map(|p| Decimal::ONE - p)),
+            ] {
+                if let Some(token) = token {
+                    let leg = QuoteLeg {
+                        condition_id: &market.condition_id,
+                        outcome,
+                        token,
+                    };
+                    self.requote(&leg, fair, &params, trader).await;
+                }
+            }
+        }
+    }
+
+    /// Cancel every live quote
+    pub async fn pull_all(&self, trader: &Trader) {
+        let ids: Vec<String> = {
+            let quotes = self.quotes.lock().await;
+            quotes
+                .values()
+                .flat_map(|q| [q.bid.as_ref(), q.ask.as_ref()])
+                .flatten()
+                .map(|q| q.order_id.clone())
+                .collect()
+        };
+
+        if ids.is_empty() {
+            return;
+        }
+
+        // Quotes stay tracked until the cancel lands, so the next pull retries
+        match trader.cancel_orders(&ids).await {
+            Ok(()) => self.forget(&ids).await,
+            Err(e) => warn!("Failed to pull quotes: {}", e),
+        }
+    }
+
+    /// Forget live quotes after an account-wide cancel
+    pub async fn clear_quotes(&self) {
+        self.quotes.lock().await.clear();
+    }
+
+    /// Drop every quote on a market after a market-wide cancel
+    async fn forget_market(&self, condition_id: &str) {
+        for q in self.quotes.lock().await.values_mut() {
+            if q.condition_id == condition_id {
+                q.bid = None;
+                q.ask = None;
+            }
+        }
+    }
+
+    /// Drop quotes whose cancel went through
+    async fn forget(&self, order_ids: &[String]) {
+        let mut quotes = self.quotes.lock().await;
+
+        for q in quotes.values_mut() {
+            for side in [&mut q.bid, &mut q.ask] {
+                if side.as_ref().is_some_and(|live| order_ids.contains(&live.order_id)) {
+                    *side = None;
+                }
+            }
+        }
+    }
+
+    /// Our live quotes, for rewards scoring
+    pub async fn resting_orders(&self) -> Vec<RestingOrder> {
+        let quotes = self.quotes.lock().await;
+        let mut orders = Vec::new();
+
+        for (token_id, q) in quotes.iter() {
+            for (side, quote) in [("BUY", &q.bid), ("SELL", &q.ask)] {
+                if let Some(quote) = quote {
+                    orders.push(RestingOrder {
+                        order_id: quote.order_id.clone(),
+                        token_id: token_id.clone(),
+                        side: side.to_string(),
+                        price: quote.price,
+                        size: quote.size,
+                    });
+                }
+            }
+        }
+
+        orders
+    }
+
+    // ==================================================
+    // QUOTING
+    // ==================================================
+    async fn requote(
+        &self,
+        leg: &QuoteLeg<'_>,
+        fair: Option<Decimal>,
+        params: &MarketParams,
+        trader: &Trader,
+    ) {
+        let token = leg.token;
+        let Some(cached) = self.price_cache.get(&token.token_id).await else {
+            return;
+        };
+
+        let reference = match (fair, token.bid, token.ask) {
+            (Some(fair), _, _) => fair,
+            (None, Some(bid), Some(ask)) => (bid + ask) / dec!(2),
+            _ => return,
+        };
+
+        let max_age = std::time::Duration::from_millis(self.config.max_quote_age_ms);
+        let threshold = Decimal::from_f64(self.config.requote_threshold).unwrap_or(dec!(0.01));
+
+        {
+            let quotes = self.quotes.lock().await;
+            if let Some(q) = quotes.get(&token.token_id) {
+                let fresh = q.quoted_at.map(|t| t.elapsed() < max_age).unwrap_or(false);
+                let book_unchanged = q.book_update_ms == cached.last_update_ms;
+                let small_move = (reference - q.reference).abs() < threshold;
+
+                if fresh && (book_unchanged || small_move) {
+                    return;
+                }
+            }
+        }
+
+        let inventory = self
+            .portfolio
+            .position(&token.token_id)
+            .await
+            .map(|p| p.size)
+            .unwrap_or(Decimal::ZERO);
+
+        // Asks can't sell more than the exchange can draw on
+        let sellable = match self.account.token_balance(&token.token_id).await {
+            Some(balance) => inventory.min(balance.available()),
+            None => inventory,
+        };
+
+        let desired = self.compute_quotes(reference, inventory, sellable, params);
+
+        // ---------------- cancel ----------------
+        let stale: Vec<String> = {
+            let quotes = self.quotes.lock().await;
+            quotes
+                .get(&token.token_id)
+                .into_iter()
+                .flat_map(|q| [q.bid.as_ref(), q.ask.as_ref()])
+                .flatten()
+                .map(|q| q.order_id.clone())
+                .collect()
+        };
+
+        // Old quotes stay tracked until cancelled, so a failure retries next snapshot
+        if !stale.is_empty() {
+            if let Err(e) = trader.cancel_orders(&stale).await {
+                warn!("Cancel before re-quote failed: {}", e);
+                return;
+            }
+            self.forget(&stale).await;
+        }
+
+        // ---------------- replace ----------------
+        let bid = match desired.bid {
+            Some((price, size)) => self.post(trader, leg, "BUY", price, size).await,
+            None => Ok(None),
+        };
+        let ask = match (&bid, desired.ask) {
+            (Ok(_), Some((price, size))) => self.post(trader, leg, "SELL", price, size).await,
+            _ => Ok(None),
+        };
+
+        // An untrackable quote took the whole market down with it
+        let (Ok(bid), Ok(ask)) = (bid, ask) else {
+            return;
+        };
+
+        info!(
+            "📝 Quoted {} | ref={} bid={:?} ask={:?} inv={}",
+            token.token_id,
+            reference,
+            bid.as_ref().map(|q| q.price),
+            ask.as_ref().map(|q| q.price),
+            inventory
+        );
+
+        let mut quotes = self.quotes.lock().await;
+        let q = quotes.entry(token.token_id.clone()).or_default();
+        q.condition_id = leg.condition_id.to_string();
+        q.bid = bid;
+        q.ask = ask;
+        q.reference = reference;
+        q.book_update_ms = cached.last_update_ms;
+        q.quoted_at = Some(Instant::now());
+    }
+
+    /// Quotes around `reference`, skewed away from held inventory.
+    /// Asks are sized to `sellable`.
+    fn compute_quotes(
+        &self,
+        reference: Decimal,
+        inventory: Decimal,
+        sellable: Decimal,
+        params: &MarketParams,
+    ) -> QuotePair {
+        let cfg = &self.config;
+        let max_position = Decimal::from_f64(cfg.max_position).unwrap_or(Decimal::ZERO);
+        let mut half_spread = Decimal::from_f64(cfg.half_spread).unwrap_or(dec!(0.02));
+        let skew = Decimal::from_f64(cfg.inventory_skew).unwrap_or(Decimal::ZERO);
+        let mut size = Decimal::from_f64(cfg.quote_size).unwrap_or(Decimal::ZERO);
+
+        if cfg.reward_eligible && params.reward_max_spread > Decimal::ZERO {
+            half_spread = half_spread.min(params.reward_max_spread - params.tick);
+            size = size.max(params.reward_min_size);
+        }
+        half_spread = half_spread.max(params.tick);
+        size = size.max(params.min_order_size);
+
+        let shift = if max_position > Decimal::ZERO {
+            skew * (inventory / max_position).min(Decimal::ONE)
+        } else {
+            Decimal::ZERO
+        };
+
+        let floor = params.tick;
+        let ceil = Decimal::ONE - params.tick;
+
+        let bid_price = round_down(reference - half_spread - shift, params.tick).max(floor);
+        let ask_price = round_up(reference + half_spread - shift, params.tick).min(ceil);
+
+        let bid_size = size.min(max_position - inventory);
+        let ask_size = size.min(sellable);
+
+        QuotePair {
+            bid: (bid_size >= params.min_order_size && bid_price < ask_price)
+                .then_some((bid_price, bid_size.floor())),
+            ask: (ask_size >= params.min_order_size && ask_price > bid_price)
+                .then_some((ask_price, ask_size.floor())),
+        }
+    }
+
+    /// Post one quote; `Ok(None)` when it was rejected. `Err` means the
+    /// exchange took it without an order id, so it may rest where we can't
+    /// cancel it by id — every order on the market is cancelled instead.
+    async fn post(
+        &self,
+        trader: &Trader,
+        leg: &QuoteLeg<'_>,
+        side: &str,
+        price: Decimal,
+        size: Decimal,
+    ) -> Result<Option<LiveQuote>> {
+        let intent = OrderIntent {
+            strategy: "market_maker",
+            condition_id: leg.condition_id.to_string(),
+            token_id: leg.token.token_id.clone(),
+            outcome: leg.outcome.to_string(),
+            side: side.to_string(),
+            price,
+            size,
+            bundle_id: None,
+        };
+
+        match trader.place_limit_order(&intent, "GTC").await {
+            Ok(OrderResponse {
+                order_id: Some(order_id),
+                ..
+            }) => Ok(Some(LiveQuote {
+                order_id,
+                price,
+                size,
+            })),
+            Ok(_) => {
+                warn!(
+                    "Quote {} {} @ {} accepted without an order id — cancelling market {}",
+                    side, leg.token.token_id, price, leg.condition_id
+                );
+
+                match trader.cancel_market(leg.condition_id).await {
+                    Ok(()) => self.forget_market(leg.condition_id).await,
+                    Err(e) => error!("🚨 Cancel of {} failed: {}", leg.condition_id, e),
+                }
+                Err(anyhow!("quote accepted without an order id"))
+            }
+            Err(e) => {
+                warn!(
+                    "Quote {} {} @ {} failed: {}",
+                    side, leg.token.token_id, price, e
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn pull_market(&self, market: &MarketData, trader: &Trader) {
+        let mut ids = Vec::new();
+        {
+            let quotes = self.quotes.lock().await;
+            for token in [market.up_token.as_ref(), market.down_token.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                if let Some(q) = quotes.get(&token.token_id) {
+                    ids.extend(q.bid.as_ref().map(|q| q.order_id.clone()));
+                    ids.extend(q.ask.as_ref().map(|q| q.order_id.clone()));
+                }
+            }
+        }
+
+        if ids.is_empty() {
+            return;
+        }
+
+        info!("🧹 Pulling {} quotes on {} before expiry", ids.len(), market.market_name);
+
+        match trader.cancel_orders(&ids).await {
+            Ok(()) => self.forget(&ids).await,
+            Err(e) => warn!("Failed to pull quotes: {}", e),
+        }
+    }
+
+    // ==================================================
+    // HELPERS
+    // ==================================================
+    fn fair_up_price(&self, spot: Option<&SpotMove>, meta: &MarketMeta) -> Option<Decimal> {
+        let model = self.fair_value.as_ref()?;
+        let spot = spot?;
+
+        // Window mismatch, or no true open price for this window
+        if spot.window_start_unix + 900 != meta.end_time_unix || spot.partial {
+            return None;
+        }
+
+        model
+            .fair_value(spot, meta.seconds_to_expiry())
+            .map(|fv| fv.up)
+    }
+
+    async fn market_params(&self, condition_id: &str) -> Option<MarketParams> {
+        if let Some(p) = self.params.lock().await.get(condition_id) {
+            return Some(p.clone());
+        }
+
+        let details = self.account.market(condition_id).await?;
+
+        let params = MarketParams {
+            tick: details.minimum_tick_size,
+            min_order_size: details.minimum_order_size,
+            reward_max_spread: details.rewards.max_spread / dec!(100),
+            reward_min_size: details.rewards.min_size,
+        };
+
+        self.params
+            .lock()
+            .await
+            .insert(condition_id.to_string(), params.clone());
+
+        Some(params)
+    }
+}
+
+fn round_down(price: Decimal, tick: Decimal) -> Decimal {
+    if tick.is_zero() {
+        return price;
+    }
+    (price / tick).floor() * tick
+}
+
+fn round_up(price: Decimal, tick: Decimal) -> Decimal {
+    if tick.is_zero() {
+        return price;
+    }
+    (price / tick).ceil() * tick
+}
@@ -1,31 +1,85 @@
+pub mod expiry;
+pub mod market_maker;
+pub mod sizing;
+
+use crate::config::ExpiryRules;
+use crate::control::RuntimeControls;
 use crate::domain::*;
+use crate::latency::{Stage, Trace};
+use crate::model::fair_value::{FairValueModel, MispricedLeg};
 use crate::monitor::MarketSnapshot;
+use expiry::ExpiryPhase;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use std::sync::Arc;
+
+/// Bundle bound when the book feed doesn't report level sizes
+const UNKNOWN_DEPTH_BUNDLES: u64 = 1_000;
 
 #[derive(Clone)]
 pub struct ArbitrageDetector {
-    min_profit_threshold: Decimal,
+    // min_profit_threshold lives here so operators can change it live
+    controls: Arc<RuntimeControls>,
+    // Falls back to the live min_profit_threshold when unset
+    early_min_profit_threshold: Option<Decimal>,
+    expiry: ExpiryRules,
+    fair_value: Option<FairValueModel>,
 }
 
 impl ArbitrageDetector {
-    pub fn new(min_profit_threshold: f64) -> Self {
+    pub fn new(controls: Arc<RuntimeControls>, expiry: ExpiryRules) -> Self {
+        let early_min_profit_threshold = expiry
+            .early_min_profit_threshold
+            .and_then(Decimal::from_f64);
+
         Self {
-            min_profit_threshold: Decimal::from_f64(min_profit_threshold)
-                .unwrap_or(dec!(0)),
+            controls,
+            early_min_profit_threshold,
+            expiry,
+            fair_value: None,
         }
     }
 
+    pub fn with_fair_value(mut self, model: FairValueModel) -> Self {
+        self.fair_value = Some(model);
+        self
+    }
+
+    pub fn expiry_phase(&self, snapshot: &MarketSnapshot) -> ExpiryPhase {
+        self.expiry.phase(snapshot.seconds_to_expiry())
+    }
+
     /// TRUE arbitrage only:
     /// ETH_UP + BTC_DOWN < 1
     /// ETH_DOWN + BTC_UP < 1
+    ///
+    /// Bundles are sized to `available_usdc`.
     pub fn detect_opportunities(
         &self,
         snapshot: &MarketSnapshot,
+        available_usdc: Decimal,
     ) -> Vec<ArbitrageOpportunity> {
         let mut opportunities = Vec::new();
 
+        // =====================================================
+        // STALE BOOK GATE
+        // =====================================================
+        if !snapshot.eth_freshness.is_fresh() || !snapshot.btc_freshness.is_fresh() {
+            return opportunities;
+        }
+
+        // =====================================================
+        // TIME-TO-EXPIRY GATE
+        // =====================================================
+        let threshold = match self.expiry_phase(snapshot) {
+            ExpiryPhase::Early => self
+                .early_min_profit_threshold
+                .unwrap_or_else(|| self.controls.min_profit_threshold()),
+            ExpiryPhase::Open => self.controls.min_profit_threshold(),
+            _ => return opportunities,
+        };
+
         // =====================================================
         // ETH UP + BTC DOWN
         // =====================================================
@@ -36,8 +90,12 @@ impl ArbitrageDetector {
             if let Some(o) = self.build_opportunity(
                 eth,
                 btc,
+                ("Up", "Down"),
                 &snapshot.eth_market.condition_id,
                 &snapshot.btc_market.condition_id,
+                threshold,
+                available_usdc,
+                &snapshot.trace,
             ) {
                 opportunities.push(o);
             }
@@ -53,8 +111,12 @@ impl ArbitrageDetector {
             if let Some(o) = self.build_opportunity(
                 eth,
                 btc,
+                ("Down", "Up"),
                 &snapshot.eth_market.condition_id,
                 &snapshot.btc_market.condition_id,
+                threshold,
+                available_usdc,
+                &snapshot.trace,
             ) {
                 opportunities.push(o);
             }
@@ -63,13 +125,59 @@ impl ArbitrageDetector {
         opportunities
     }
 
+    /// Legs whose book price is through spot-implied fair value
+    pub fn detect_mispriced_legs(
+        &self,
+        snapshot: &MarketSnapshot,
+    ) -> Vec<MispricedLeg> {
+        let Some(model) = self.fair_value.as_ref() else {
+            return Vec::new();
+        };
+
+        let mut legs = Vec::new();
+
+        for (market, meta, spot, freshness) in [
+            (&snapshot.eth_market, &snapshot.eth_market_meta, &snapshot.eth_spot, snapshot.eth_freshness),
+            (&snapshot.btc_market, &snapshot.btc_market_meta, &snapshot.btc_spot, snapshot.btc_freshness),
+        ] {
+            let Some(spot) = spot.as_ref() else {
+                continue;
+            };
+
+            if !freshness.is_fresh() {
+                continue;
+            }
+
+            // Spot window must match the market window
+            if spot.window_start_unix + 900 != meta.end_time_unix {
+                continue;
+            }
+
+            // No true open price for this window, so no reference to price against
+            if spot.partial {
+                continue;
+            }
+
+            if let Some(fair) = model.fair_value(spot, meta.seconds_to_expiry()) {
+                legs.extend(model.mispriced_legs(market, &fair));
+            }
+        }
+
+        legs
+    }
+
     /// Bundle sizing (SAFE, INTEGER ONLY)
+    #[allow(clippy::too_many_arguments)]
     fn build_opportunity(
         &self,
         eth_token: &TokenPrice,
         btc_token: &TokenPrice,
+        (eth_outcome, btc_outcome): (&'static str, &'static str),
         eth_condition_id: &str,
         btc_condition_id: &str,
+        min_profit_threshold: Decimal,
+        available_usdc: Decimal,
+        trace: &Trace,
     ) -> Option<ArbitrageOpportunity> {
         // -------------------------------------------------
         // USE ASK PRICE (worst-case entry)
@@ -86,28 +194,30 @@ impl ArbitrageDetector {
 
         let profit_per_bundle = dec!(1.0) - bundle_cost;
 
-        if profit_per_bundle < self.min_profit_threshold {
+        if profit_per_bundle < min_profit_threshold {
             return None;
         }
 
         // -------------------------------------------------
-        // CAPITAL (TEMP PLACEHOLDER — $10)
+        // CAPITAL (balance the exchange can draw on)
         // -------------------------------------------------
-        let available_usdc = dec!(10);
-
         let max_by_capital = (available_usdc / bundle_cost)
             .floor()
             .to_u64()
             .unwrap_or(0);
 
         // -------------------------------------------------
-        // 🔒 LIQUIDITY PLACEHOLDER (SAFE)
-        // (REAL DEPTH COMES LATER)
+        // LIQUIDITY (top of both books)
         // -------------------------------------------------
-        let eth_liquidity: u64 = 1_000;
-        let btc_liquidity: u64 = 1_000;
+        let depth = eth_token
+            .ask_size
+            .zip(btc_token.ask_size)
+            .map(|(eth, btc)| eth.min(btc));
 
-        let max_by_liquidity = std::cmp::min(eth_liquidity, btc_liquidity);
+        // Without sizes from the feed, fall back to a fixed bound
+        let max_by_liquidity = depth
+            .map(|d| d.floor().to_u64().unwrap_or(0))
+            .unwrap_or(UNKNOWN_DEPTH_BUNDLES);
 
         // -------------------------------------------------
         // FINAL SHARES (INTEGER ONLY)
@@ -120,6 +230,10 @@ impl ArbitrageDetector {
 
         let shares_dec = Decimal::from(shares);
 
+        // One correlation id per opportunity
+        let mut trace = trace.fork();
+        trace.mark(Stage::Detection);
+
         let total_cost = bundle_cost * shares_dec;
         let expected_profit = profit_per_bundle * shares_dec;
 
@@ -129,12 +243,16 @@ impl ArbitrageDetector {
 
             eth_up_token_id: eth_token.token_id.clone(),
             btc_down_token_id: btc_token.token_id.clone(),
+            eth_outcome,
+            btc_outcome,
 
             eth_up_price: eth_price,
             btc_down_price: btc_price,
 
             total_cost,
             expected_profit,
+            depth,
+            trace,
         })
     }
 }
@@ -0,0 +1,157 @@
+use crate::config::SizingPolicy;
+
+/// Payoff distribution of one bundle: an Up leg on one coin and a Down
+/// leg on the other, each paying $1 if it wins.
+#[derive(Debug, Clone, Copy)]
+pub struct BundleOutcomes {
+    /// Price of one bundle
+    pub cost: f64,
+    /// (probability, payoff) for both legs / one leg / no leg winning
+    pub outcomes: [(f64, f64); 3],
+}
+
+impl BundleOutcomes {
+    /// Leg prices double as win probabilities. With probability
+    /// `correlation` ETH and BTC move together and exactly one leg pays;
+    /// otherwise they resolve independently.
+    pub fn estimate(eth_price: f64, btc_price: f64, correlation: f64) -> Self {
+        let rho = correlation.clamp(0.0, 1.0);
+        let (a, b) = (eth_price.clamp(0.0, 1.0), btc_price.clamp(0.0, 1.0));
+
+        let both = (1.0 - rho) * a * b;
+        let none = (1.0 - rho) * (1.0 - a) * (1.0 - b);
+
+        Self {
+            cost: a + b,
+            outcomes: [(both, 2.0), (1.0 - both - none, 1.0), (none, 0.0)],
+        }
+    }
+
+    /// Expected PnL per bundle
+    pub fn mean(&self) -> f64 {
+        self.outcomes.iter().map(|(p, x)| p * (x - self.cost)).sum()
+    }
+
+    /// PnL variance per bundle
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        self.outcomes
+            .iter()
+            .map(|(p, x)| p * (x - self.cost - mean).powi(2))
+            .sum()
+    }
+
+    /// Share of bankroll maximising expected log growth
+    pub fn kelly(&self) -> f64 {
+        if self.cost <= 0.0 || self.mean() <= 0.0 {
+            return 0.0;
+        }
+
+        let growth = |f: f64| -> f64 {
+            self.outcomes
+                .iter()
+                .filter(|(p, _)| *p > 0.0)
+                .map(|(p, x)| p * (1.0 + f * (x - self.cost) / self.cost).ln())
+                .sum()
+        };
+
+        // Growth is concave in f; losing every leg wipes the stake, so stay below 1
+        let (mut lo, mut hi) = (0.0, 0.999);
+        for _ in 0..100 {
+            let m1 = lo + (hi - lo) / 3.0;
+            let m2 = hi - (hi - lo) / 3.0;
+            if growth(m1) < growth(m2) {
+                lo = m1;
+            } else {
+                hi = m2;
+            }
+        }
+
+        (lo + hi) / 2.0
+    }
+}
+
+impl SizingPolicy {
+    /// Bundles to buy with `bankroll` USDC, before depth and risk caps.
+    /// `None` means the fixed `max_position_size` rule applies.
+    pub fn bundles(&self, eth_price: f64, btc_price: f64, bankroll: f64) -> Option<f64> {
+        match *self {
+            SizingPolicy::Fixed => None,
+            SizingPolicy::Kelly { fraction, correlation } => {
+                let outcomes = BundleOutcomes::estimate(eth_price, btc_price, correlation);
+                if outcomes.cost <= 0.0 {
+                    return Some(0.0);
+                }
+
+                let stake = bankroll * outcomes.kelly() * fraction.clamp(0.0, 1.0);
+                Some(stake / outcomes.cost)
+            }
+            SizingPolicy::VolatilityTarget { target_usdc, correlation } => {
+                let outcomes = BundleOutcomes::estimate(eth_price, btc_price, correlation);
+                if outcomes.mean() <= 0.0 {
+                    return Some(0.0);
+                }
+
+                let sd = outcomes.variance().sqrt();
+                Some(if sd > 0.0 { target_usdc / sd } else { 0.0 })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn growth(outcomes: &BundleOutcomes, f: f64) -> f64 {
+        outcomes
+            .outcomes
+            .iter()
+            .filter(|(p, _)| *p > 0.0)
+            .map(|(p, x)| p * (1.0 + f * (x - outcomes.cost) / outcomes.cost).ln())
+            .sum()
+    }
+
+    #[test]
+    fn kelly_maximises_log_growth() {
+        let outcomes = BundleOutcomes::estimate(0.45, 0.45, 0.5);
+        let f = outcomes.kelly();
+
+        assert!(f > 0.0 && f < 1.0);
+        for other in [f - 0.05, f + 0.05, f / 2.0, (f * 2.0).min(0.99)] {
+            assert!(growth(&outcomes, f) >= growth(&outcomes, other));
+        }
+    }
+
+    #[test]
+    fn independent_legs_have_no_edge() {
+        let outcomes = BundleOutcomes::estimate(0.45, 0.45, 0.0);
+
+        assert!(outcomes.mean().abs() < 1e-12);
+        assert_eq!(outcomes.kelly(), 0.0);
+
+        let kelly = SizingPolicy::Kelly {
+            fraction: 0.5,
+            correlation: 0.0,
+        };
+        assert_eq!(kelly.bundles(0.45, 0.45, 1_000.0), Some(0.0));
+    }
+
+    #[test]
+    fn volatility_target_caps_pnl_deviation() {
+        let policy = SizingPolicy::VolatilityTarget {
+            target_usdc: 50.0,
+            correlation: 0.5,
+        };
+        let bundles = policy.bundles(0.45, 0.45, 1_000.0).unwrap();
+        let sd = BundleOutcomes::estimate(0.45, 0.45, 0.5).variance().sqrt();
+
+        assert!(bundles > 0.0);
+        assert!((bundles * sd - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fixed_policy_defers_to_position_size() {
+        assert_eq!(SizingPolicy::Fixed.bundles(0.45, 0.45, 1_000.0), None);
+    }
+}
@@ -0,0 +1,623 @@
+use crate::account::AccountState;
+use crate::client::PolymarketClient;
+use crate::domain::ApiPosition;
+use crate::portfolio::Portfolio;
+use crate::risk::RiskManager;
+use crate::wallet::signer::WalletSigner;
+
+use anyhow::{anyhow, Result};
+use ethers::prelude::*;
+use log::{info, warn};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+abigen!(
+    ConditionalTokens,
+    r#"[
+        function redeemPositions(address collateralToken, bytes32 parentCollectionId, bytes32 conditionId, uint256[] indexSets)
+        function mergePositions(address collateralToken, bytes32 parentCollectionId, bytes32 conditionId, uint256[] partition, uint256 amount)
+    ]"#
+);
+
+abigen!(
+    NegRiskAdapter,
+    r#"[
+        function redeemPositions(bytes32 conditionId, uint256[] amounts)
+        function mergePositions(bytes32 conditionId, uint256 amount)
+    ]"#
+);
+
+abigen!(
+    ProxyWalletFactory,
+    r#"[
+        struct ProxyTxn { uint8 typeCode; address to; uint256 value; bytes data; }
+        function proxy(ProxyTxn[] calls) payable returns (bytes[] returnValues)
+    ]"#
+);
+
+abigen!(
+    Erc20,
+    r#"[
+        function allowance(address owner, address spender) view returns (uint256)
+        function approve(address spender, uint256 amount) returns (bool)
+    ]"#
+);
+
+abigen!(
+    Erc1155,
+    r#"[
+        function isApprovedForAll(address owner, address operator) view returns (bool)
+        function setApprovalForAll(address operator, bool approved)
+    ]"#
+);
+
+abigen!(
+    CtfExchange,
+    r#"[
+        function nonces(address maker) view returns (uint256)
+        function incrementNonce()
+    ]"#
+);
+
+type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// ProxyTxn.typeCode for a plain CALL
+const CALL: u8 = 1;
+
+/// Binary markets: outcome slots 0b01 and 0b10
+const BINARY_INDEX_SETS: [u64; 2] = [1, 2];
+
+/* =======================
+CONFIG
+======================= */
+
+/// Who holds the tokens and sends the calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletMode {
+    /// Tokens sit on the signer itself
+    Eoa,
+    /// Tokens sit on a Polymarket proxy wallet; calls go through its factory
+    Proxy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasSettings {
+    pub gas_limit: Option<u64>,
+    pub max_fee_per_gas_gwei: Option<f64>,
+    pub max_priority_fee_per_gas_gwei: Option<f64>,
+}
+
+/// Defaults are Polygon mainnet; point `rpc_url` and the addresses at a
+/// local anvil / hardhat fork to test.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CtfConfig {
+    pub rpc_url: String,
+    pub mode: WalletMode,
+
+    pub ctf_address: Address,
+    pub collateral_address: Address,
+    pub neg_risk_adapter_address: Address,
+    pub proxy_factory_address: Address,
+    pub exchange_address: Address,
+    pub neg_risk_exchange_address: Address,
+
+    /// Redemptions per proxy transaction
+    pub max_batch: usize,
+    pub gas: GasSettings,
+
+    /// Redeem resolved positions this often (off when unset)
+    pub auto_redeem_secs: Option<u64>,
+
+    /// Merge complete Up+Down sets back to USDC just before window close
+    pub auto_merge: bool,
+}
+
+impl Default for CtfConfig {
+    fn default() -> Self {
+        let addr = |s: &str| Address::from_str(s).unwrap();
+
+        Self {
+            rpc_url: "https://polygon-rpc.com".into(),
+            mode: WalletMode::Proxy,
+            ctf_address: addr("0x4D97DCd97eC945f40cF65F87097ACe5EA0476045"),
+            collateral_address: addr("0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174"),
+            neg_risk_adapter_address: addr("0xd91E80cF2E7be2e162c6513ceD06f1dD0dA35296"),
+            proxy_factory_address: addr("0xaB45c5A4B0c941a2F231C04C3f49182e1A254052"),
+            exchange_address: addr("0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E"),
+            neg_risk_exchange_address: addr("0xC5d563A36AE78145C45a50134d48A1215220f80a"),
+            max_batch: 10,
+            gas: GasSettings::default(),
+            auto_redeem_secs: None,
+            auto_merge: false,
+        }
+    }
+}
+
+/* =======================
+REDEMPTIONS
+======================= */
+
+#[derive(Debug, Clone)]
+pub struct Redemption {
+    pub condition_id: H256,
+    pub neg_risk: bool,
+    /// Per-outcome token amounts (6 decimals); only the neg-risk adapter needs them
+    pub amounts: [U256; 2],
+}
+
+impl Redemption {
+    /// One redemption per resolved condition we still hold tokens in
+    pub fn from_positions(positions: &[ApiPosition]) -> Vec<Redemption> {
+        let mut by_condition: BTreeMap<&str, Redemption> = BTreeMap::new();
+
+        for p in positions.iter().filter(|p| p.redeemable && !p.size.is_zero()) {
+            let Ok(condition_id) = H256::from_str(&p.condition_id) else {
+                warn!("Skipping redemption for bad condition id {}", p.condition_id);
+                continue;
+            };
+
+            let r = by_condition.entry(&p.condition_id).or_insert(Redemption {
+                condition_id,
+                neg_risk: p.negative_risk,
+                amounts: [U256::zero(); 2],
+            });
+
+            if let Some(slot) = r.amounts.get_mut(p.outcome_index as usize) {
+                *slot = to_token_units(p.size);
+            }
+        }
+
+        by_condition.into_values().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Merge {
+    pub condition_id: H256,
+    pub neg_risk: bool,
+    /// Complete sets to merge (6 decimals)
+    pub amount: U256,
+}
+
+/// Approvals one spender needs before the exchange can settle our orders
+#[derive(Debug, Clone)]
+pub struct SpenderAllowance {
+    pub name: &'static str,
+    pub spender: Address,
+    /// USDC the spender may pull (6 decimals)
+    pub usdc_allowance: U256,
+    /// May move our conditional tokens
+    pub ctf_approved: bool,
+}
+
+impl SpenderAllowance {
+    /// Anything short of an unlimited approval eventually runs out
+    pub fn usdc_missing(&self) -> bool {
+        self.usdc_allowance < U256::MAX / 2
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.usdc_missing() && self.ctf_approved
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AllowanceReport {
+    pub owner: Address,
+    pub spenders: Vec<SpenderAllowance>,
+}
+
+impl AllowanceReport {
+    pub fn is_complete(&self) -> bool {
+        self.spenders.iter().all(|s| s.is_complete())
+    }
+
+    /// One line per missing approval
+    pub fn missing(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+
+        for s in &self.spenders {
+            if s.usdc_missing() {
+                missing.push(format!(
+                    "USDC allowance for {} ({:?}) is {}",
+                    s.name, s.spender, s.usdc_allowance
+                ));
+            }
+            if !s.ctf_approved {
+                missing.push(format!(
+                    "conditional tokens not approved for {} ({:?})",
+                    s.name, s.spender
+                ));
+            }
+        }
+
+        missing
+    }
+
+    pub fn log(&self) {
+        if self.is_complete() {
+            info!("✅ Allowances set for {:?}", self.owner);
+            return;
+        }
+
+        for m in self.missing() {
+            warn!("⚠️ Missing approval: {}", m);
+        }
+        warn!("⚠️ Orders will fail until approved — run with `setup-allowances`");
+    }
+}
+
+/// Shares → 6-decimal on-chain units
+fn to_token_units(size: rust_decimal::Decimal) -> U256 {
+    let units = (size * rust_decimal::Decimal::from(1_000_000))
+        .trunc()
+        .to_u128()
+        .unwrap_or(0);
+    U256::from(units)
+}
+
+fn gwei(v: f64) -> U256 {
+    U256::from((v * 1e9) as u128)
+}
+
+/* =======================
+CLIENT
+======================= */
+
+/// Conditional Tokens calls signed by the wallet signer
+pub struct CtfClient {
+    config: CtfConfig,
+    client: Arc<Client>,
+}
+
+impl CtfClient {
+    pub fn new(config: CtfConfig, signer: &WalletSigner) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(config.rpc_url.as_str())?;
+        let client = Arc::new(SignerMiddleware::new(provider, signer.local_wallet()));
+
+        Ok(Self { config, client })
+    }
+
+    pub async fn redeem(&self, redemptions: &[Redemption]) -> Result<Vec<TxHash>> {
+        let calls = redemptions
+            .iter()
+            .map(|r| self.redeem_call(r))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.send_calls(calls).await
+    }
+
+    fn redeem_call(&self, r: &Redemption) -> Result<(Address, Bytes)> {
+        if r.neg_risk {
+            let adapter =
+                NegRiskAdapter::new(self.config.neg_risk_adapter_address, self.client.clone());
+            let data = adapter
+                .redeem_positions(r.condition_id.into(), r.amounts.to_vec())
+                .calldata()
+                .ok_or_else(|| anyhow!("Failed to encode redeemPositions"))?;
+
+            return Ok((self.config.neg_risk_adapter_address, data));
+        }
+
+        let ctf = ConditionalTokens::new(self.config.ctf_address, self.client.clone());
+        let data = ctf
+            .redeem_positions(
+                self.config.collateral_address,
+                [0u8; 32],
+                r.condition_id.into(),
+                BINARY_INDEX_SETS.iter().map(|i| U256::from(*i)).collect(),
+            )
+            .calldata()
+            .ok_or_else(|| anyhow!("Failed to encode redeemPositions"))?;
+
+        Ok((self.config.ctf_address, data))
+    }
+
+    pub async fn merge(&self, merges: &[Merge]) -> Result<Vec<TxHash>> {
+        let calls = merges
+            .iter()
+            .map(|m| self.merge_call(m))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.send_calls(calls).await
+    }
+
+    fn merge_call(&self, m: &Merge) -> Result<(Address, Bytes)> {
+        if m.neg_risk {
+            let adapter =
+                NegRiskAdapter::new(self.config.neg_risk_adapter_address, self.client.clone());
+            let data = adapter
+                .merge_positions(m.condition_id.into(), m.amount)
+                .calldata()
+                .ok_or_else(|| anyhow!("Failed to encode mergePositions"))?;
+
+            return Ok((self.config.neg_risk_adapter_address, data));
+        }
+
+        let ctf = ConditionalTokens::new(self.config.ctf_address, self.client.clone());
+        let data = ctf
+            .merge_positions(
+                self.config.collateral_address,
+                [0u8; 32],
+                m.condition_id.into(),
+                BINARY_INDEX_SETS.iter().map(|i| U256::from(*i)).collect(),
+                m.amount,
+            )
+            .calldata()
+            .ok_or_else(|| anyhow!("Failed to encode mergePositions"))?;
+
+        Ok((self.config.ctf_address, data))
+    }
+
+    pub fn auto_merge(&self) -> bool {
+        self.config.auto_merge
+    }
+
+    // ==================================================
+    // ALLOWANCES
+    // ==================================================
+    /// Address holding the tokens: the signer, or the proxy wallet
+    pub fn holder(&self, proxy_wallet: &str) -> Result<Address> {
+        match self.config.mode {
+            WalletMode::Eoa => Ok(self.client.address()),
+            WalletMode::Proxy => Address::from_str(proxy_wallet)
+                .map_err(|e| anyhow!("Bad proxy wallet {}: {}", proxy_wallet, e)),
+        }
+    }
+
+    fn spenders(&self) -> [(&'static str, Address); 3] {
+        [
+            ("CTF Exchange", self.config.exchange_address),
+            ("Neg-Risk Exchange", self.config.neg_risk_exchange_address),
+            ("Neg-Risk Adapter", self.config.neg_risk_adapter_address),
+        ]
+    }
+
+    pub async fn check_allowances(&self, owner: Address) -> Result<AllowanceReport> {
+        let usdc = Erc20::new(self.config.collateral_address, self.client.clone());
+        let ctf = Erc1155::new(self.config.ctf_address, self.client.clone());
+
+        let mut spenders = Vec::new();
+
+        for (name, spender) in self.spenders() {
+            spenders.push(SpenderAllowance {
+                name,
+                spender,
+                usdc_allowance: usdc.allowance(owner, spender).call().await?,
+                ctf_approved: ctf.is_approved_for_all(owner, spender).call().await?,
+            });
+        }
+
+        Ok(AllowanceReport { owner, spenders })
+    }
+
+    fn exchange(&self, neg_risk: bool) -> Address {
+        if neg_risk {
+            self.config.neg_risk_exchange_address
+        } else {
+            self.config.exchange_address
+        }
+    }
+
+    /// The maker's current order nonce on one exchange
+    pub async fn exchange_nonce(&self, maker: Address, neg_risk: bool) -> Result<U256> {
+        let exchange = CtfExchange::new(self.exchange(neg_risk), self.client.clone());
+        Ok(exchange.nonces(maker).call().await?)
+    }
+
+    /// Bump the maker nonce on both exchanges, voiding all signed orders
+    pub async fn increment_nonces(&self) -> Result<Vec<TxHash>> {
+        let mut calls = Vec::new();
+
+        for neg_risk in [false, true] {
+            let exchange = CtfExchange::new(self.exchange(neg_risk), self.client.clone());
+            let data = exchange
+                .increment_nonce()
+                .calldata()
+                .ok_or_else(|| anyhow!("Failed to encode incrementNonce"))?;
+            calls.push((self.exchange(neg_risk), data));
+        }
+
+        self.send_calls(calls).await
+    }
+
+    /// Send whatever approvals the report is missing
+    pub async fn approve_missing(&self, report: &AllowanceReport) -> Result<Vec<TxHash>> {
+        let usdc = Erc20::new(self.config.collateral_address, self.client.clone());
+        let ctf = Erc1155::new(self.config.ctf_address, self.client.clone());
+
+        let mut calls = Vec::new();
+
+        for s in &report.spenders {
+            if s.usdc_missing() {
+                let data = usdc
+                    .approve(s.spender, U256::MAX)
+                    .calldata()
+                    .ok_or_else(|| anyhow!("Failed to encode approve"))?;
+                calls.push((self.config.collateral_address, data));
+            }
+            if !s.ctf_approved {
+                let data = ctf
+                    .set_approval_for_all(s.spender, true)
+                    .calldata()
+                    .ok_or_else(|| anyhow!("Failed to encode setApprovalForAll"))?;
+                calls.push((self.config.ctf_address, data));
+            }
+        }
+
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        info!("🔏 Sending {} approvals for {:?}", calls.len(), report.owner);
+        self.send_calls(calls).await
+    }
+
+    // ==================================================
+    // TRANSACTIONS
+    // ==================================================
+    /// EOA: one transaction per call. Proxy: up to `max_batch` calls per
+    /// transaction through the proxy wallet factory.
+    async fn send_calls(&self, calls: Vec<(Address, Bytes)>) -> Result<Vec<TxHash>> {
+        let mut hashes = Vec::new();
+
+        match self.config.mode {
+            WalletMode::Eoa => {
+                for (to, data) in calls {
+                    hashes.push(self.send(to, data).await?);
+                }
+            }
+            WalletMode::Proxy => {
+                let factory = ProxyWalletFactory::new(
+                    self.config.proxy_factory_address,
+                    self.client.clone(),
+                );
+
+                for chunk in calls.chunks(self.config.max_batch.max(1)) {
+                    let proxy_calls = chunk
+                        .iter()
+                        .map(|(to, data)| ProxyTxn {
+                            type_code: CALL,
+                            to: *to,
+                            value: U256::zero(),
+                            data: data.clone(),
+                        })
+                        .collect();
+
+                    let data = factory
+                        .proxy(proxy_calls)
+                        .calldata()
+                        .ok_or_else(|| anyhow!("Failed to encode proxy call"))?;
+
+                    hashes.push(self.send(self.config.proxy_factory_address, data).await?);
+                }
+            }
+        }
+
+        Ok(hashes)
+    }
+
+    /// Send and wait for a successful receipt
+    async fn send(&self, to: Address, data: Bytes) -> Result<TxHash> {
+        let gas = &self.config.gas;
+        let mut tx = Eip1559TransactionRequest::new().to(to).data(data);
+
+        if let Some(limit) = gas.gas_limit {
+            tx = tx.gas(limit);
+        }
+        if let Some(fee) = gas.max_fee_per_gas_gwei {
+            tx = tx.max_fee_per_gas(gwei(fee));
+        }
+        if let Some(tip) = gas.max_priority_fee_per_gas_gwei {
+            tx = tx.max_priority_fee_per_gas(gwei(tip));
+        }
+
+        let pending = self.client.send_transaction(tx, None).await?;
+        let hash = pending.tx_hash();
+
+        let receipt = pending
+            .await?
+            .ok_or_else(|| anyhow!("Transaction {:?} dropped", hash))?;
+
+        if receipt.status != Some(U64::from(1)) {
+            anyhow::bail!("Transaction {:?} reverted", hash);
+        }
+
+        Ok(hash)
+    }
+
+    // ==================================================
+    // AUTO-REDEEM
+    // ==================================================
+    pub async fn run_auto_redeem(self: Arc<Self>, api: Arc<PolymarketClient>, wallet: String) {
+        let Some(secs) = self.config.auto_redeem_secs else {
+            return;
+        };
+
+        info!("🎟 Auto-redeem every {}s", secs);
+
+        loop {
+            match api.get_positions(&wallet).await {
+                Ok(positions) => {
+                    let redemptions = Redemption::from_positions(&positions);
+
+                    if !redemptions.is_empty() {
+                        match self.redeem(&redemptions).await {
+                            Ok(hashes) => info!(
+                                "🎟 Redeemed {} resolved markets ({} txs)",
+                                redemptions.len(),
+                                hashes.len()
+                            ),
+                            Err(e) => warn!("Redemption failed: {}", e),
+                        }
+                    }
+                }
+                Err(e) => warn!("Redeemable position lookup failed: {}", e),
+            }
+
+            sleep(Duration::from_secs(secs)).await;
+        }
+    }
+}
+
+/* =======================
+MERGE HELD SETS
+======================= */
+
+/// Merge every complete Up+Down set we hold in `condition_id` back to
+/// USDC. Returns the number of sets merged (zero when there was nothing to do).
+pub async fn merge_complete_sets(
+    ctf: &CtfClient,
+    portfolio: &Portfolio,
+    account: &AccountState,
+    risk: &RiskManager,
+    condition_id: &str,
+) -> Result<rust_decimal::Decimal> {
+    let sets = portfolio.complete_sets(condition_id).await;
+    let amount = to_token_units(sets);
+
+    if amount.is_zero() {
+        return Ok(rust_decimal::Decimal::ZERO);
+    }
+
+    let neg_risk = account
+        .market(condition_id)
+        .await
+        .map(|m| m.neg_risk)
+        .unwrap_or(false);
+
+    let merge = Merge {
+        condition_id: H256::from_str(condition_id)?,
+        neg_risk,
+        amount,
+    };
+
+    let hashes = ctf.merge(&[merge]).await?;
+
+    for p in portfolio
+        .positions()
+        .await
+        .iter()
+        .filter(|p| p.condition_id == condition_id)
+    {
+        risk.on_merged(condition_id, &p.token_id, &p.outcome, p.avg_cost * sets)
+            .await;
+    }
+
+    let realized = portfolio.apply_merge(condition_id, sets).await;
+    risk.record_realized_pnl(realized).await;
+
+    info!(
+        "🔗 Merged {} sets of {} → {} USDC (pnl {}) tx {:?}",
+        sets, condition_id, sets, realized, hashes
+    );
+
+    Ok(sets)
+}
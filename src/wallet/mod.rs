@@ -1 +1,3 @@
+pub mod ctf;
+pub mod nonce;
 pub mod signer;
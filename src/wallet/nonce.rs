@@ -0,0 +1,88 @@
+use crate::wallet::ctf::CtfClient;
+
+use anyhow::Result;
+use ethers::types::{Address, TxHash, U256};
+use log::{info, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Salts stay below 2^53 so they survive JSON number parsing
+const SALT_MASK: u64 = (1 << 53) - 1;
+
+/// Order salts and the account's exchange nonces.
+///
+/// Every order gets a fresh random salt, so two orders are never
+/// identical. The nonce is the exchange's per-maker counter: orders
+/// signed with a nonce below it are void, which is what `incrementNonce`
+/// uses to cancel everything on-chain.
+pub struct NonceManager {
+    exchange: AtomicU64,
+    neg_risk_exchange: AtomicU64,
+}
+
+impl Default for NonceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NonceManager {
+    /// Nonce 0 until synced — what a fresh account has
+    pub fn new() -> Self {
+        Self {
+            exchange: AtomicU64::new(0),
+            neg_risk_exchange: AtomicU64::new(0),
+        }
+    }
+
+    pub fn salt(&self) -> U256 {
+        U256::from(rand::random::<u64>() & SALT_MASK)
+    }
+
+    /// Nonce to sign into an order on the regular or neg-risk exchange
+    pub fn nonce(&self, neg_risk: bool) -> u64 {
+        self.counter(neg_risk).load(Ordering::SeqCst)
+    }
+
+    fn counter(&self, neg_risk: bool) -> &AtomicU64 {
+        if neg_risk {
+            &self.neg_risk_exchange
+        } else {
+            &self.exchange
+        }
+    }
+
+    /// Load both nonces from chain
+    pub async fn sync(&self, ctf: &CtfClient, maker: Address) -> Result<()> {
+        for neg_risk in [false, true] {
+            let nonce = ctf.exchange_nonce(maker, neg_risk).await?.as_u64();
+            let previous = self.counter(neg_risk).swap(nonce, Ordering::SeqCst);
+
+            if previous != nonce {
+                info!(
+                    "🔢 {} nonce {} → {}",
+                    if neg_risk { "Neg-risk exchange" } else { "Exchange" },
+                    previous,
+                    nonce
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Void every order signed so far on both exchanges, on-chain.
+    /// Use when the CLOB can't be reached to cancel normally.
+    pub async fn emergency_cancel(&self, ctf: &CtfClient, maker: Address) -> Result<Vec<TxHash>> {
+        warn!("🚨 Incrementing exchange nonces — all signed orders become invalid");
+
+        let hashes = ctf.increment_nonces().await?;
+
+        if let Err(e) = self.sync(ctf, maker).await {
+            // Stale nonces would get every new order rejected
+            warn!("Nonce resync failed, bumping locally: {}", e);
+            self.exchange.fetch_add(1, Ordering::SeqCst);
+            self.neg_risk_exchange.fetch_add(1, Ordering::SeqCst);
+        }
+
+        Ok(hashes)
+    }
+}
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use ethers::prelude::*;
 use ethers::types::{H256, U256};
-use ethers::types::transaction::eip712::Eip712;
 use ethers::contract::EthAbiType;
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +20,11 @@ impl WalletSigner {
         self.wallet.address()
     }
 
+    /// For sending on-chain transactions
+    pub fn local_wallet(&self) -> LocalWallet {
+        self.wallet.clone()
+    }
+
     pub async fn sign_order(&self, order: &ClobOrder) -> Result<Signature> {
         Ok(self.wallet.sign_typed_data(order).await?)
     }
@@ -40,10 +44,11 @@ impl WalletSigner {
     verifying_contract = "0x0000000000000000000000000000000000000000"
 )]
 pub struct ClobOrder {
+    pub salt: U256,         // random, unique per order
     pub token_id: H256,     // bytes32
     pub side: u8,           // 0 = BUY, 1 = SELL
     pub price: U256,        // scaled by 1e6
     pub size: U256,         // scaled by 1e6
     pub expiration: U256,   // unix timestamp
-    pub nonce: U256,        // monotonic
+    pub nonce: U256,        // exchange nonce (incrementNonce voids lower ones)
 }
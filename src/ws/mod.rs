@@ -1,4 +1,7 @@
 use crate::cache::PriceCache;
+use crate::config::StalenessConfig;
+use crate::latency::Trace;
+use crate::metrics;
 use futures_util::{SinkExt, StreamExt};
 use log::{info, warn};
 use rust_decimal::Decimal;
@@ -11,12 +14,14 @@ pub async fn start_ws(
     ws_url: String,
     cache: PriceCache,
     token_ids: Vec<String>,
+    heartbeat: StalenessConfig,
 ) {
     loop {
         info!("🔌 Connecting to Polymarket WebSocket");
 
-        if let Err(e) = connect_and_stream(&ws_url, &cache, &token_ids).await {
+        if let Err(e) = connect_and_stream(&ws_url, &cache, &token_ids, &heartbeat).await {
             warn!("⚠️ WS error: {} — reconnecting in 2s", e);
+            metrics::global().ws_reconnects.inc();
             sleep(Duration::from_secs(2)).await;
         }
     }
@@ -26,6 +31,7 @@ async fn connect_and_stream(
     ws_url: &str,
     cache: &PriceCache,
     token_ids: &Vec<String>,
+    heartbeat: &StalenessConfig,
 ) -> anyhow::Result<()> {
     let (ws, _) = connect_async(Url::parse(ws_url)?).await?;
     let (mut write, mut read) = ws.split();
@@ -52,26 +58,58 @@ async fn connect_and_stream(
     // --------------------------------------------------
     // READ LOOP
     // --------------------------------------------------
-    while let Some(msg) = read.next().await {
-        let msg = msg?;
+    let idle_timeout = Duration::from_secs(heartbeat.ws_idle_timeout_secs);
+    let mut ping = tokio::time::interval(Duration::from_secs(heartbeat.ws_ping_interval_secs));
 
-        if let tokio_tungstenite::tungstenite::Message::Text(text) = msg {
-            if let Ok(json) = serde_json::from_str::<Value>(&text) {
-                if let Some(token_id) = json.get("token_id").and_then(|v| v.as_str()) {
-                    let bid = json
-                        .get("bid")
-                        .and_then(|v| v.as_str())
-                        .and_then(|s| s.parse::<Decimal>().ok());
+    // Only incoming messages push the deadline; our own pings don't
+    let idle = tokio::time::sleep(idle_timeout);
+    tokio::pin!(idle);
 
-                    let ask = json
-                        .get("ask")
-                        .and_then(|v| v.as_str())
-                        .and_then(|s| s.parse::<Decimal>().ok());
+    loop {
+        let msg = tokio::select! {
+            _ = ping.tick() => {
+                write
+                    .send(tokio_tungstenite::tungstenite::Message::Text("PING".into()))
+                    .await?;
+                continue;
+            }
+            _ = &mut idle => {
+                return Err(anyhow::anyhow!(
+                    "no WS messages for {}s",
+                    idle_timeout.as_secs()
+                ));
+            }
+            msg = read.next() => match msg {
+                Some(msg) => msg?,
+                None => break,
+            },
+        };
+
+        idle.as_mut().reset(tokio::time::Instant::now() + idle_timeout);
 
-                    let bids = bid.map(|b| vec![(b, Decimal::ONE)]).unwrap_or_default();
-let asks = ask.map(|a| vec![(a, Decimal::ONE)]).unwrap_or_default();
+        let received_at = std::time::Instant::now();
+        metrics::global().ws_messages.inc();
 
-cache.update(token_id, bids, asks).await;
+        if let tokio_tungstenite::tungstenite::Message::Text(text) = msg {
+            if let Ok(json) = serde_json::from_str::<Value>(&text) {
+                // Events arrive alone or batched in an array
+                let events = match json {
+                    Value::Array(events) => events,
+                    event => vec![event],
+                };
+
+                for event in &events {
+                    if let Some(book) = parse_book(event) {
+                        cache
+                            .update(
+                                &book.token_id,
+                                book.bids,
+                                book.asks,
+                                book.sizes_known,
+                                Trace::start(received_at),
+                            )
+                            .await;
+                    }
                 }
             }
         }
@@ -80,3 +118,71 @@ cache.update(token_id, bids, asks).await;
     // If we exit the read loop → WS disconnected
     Err(anyhow::anyhow!("WebSocket disconnected"))
 }
+
+/* =======================
+MESSAGE PARSING
+======================= */
+
+struct BookMessage {
+    token_id: String,
+    bids: Vec<(Decimal, Decimal)>,
+    asks: Vec<(Decimal, Decimal)>,
+    sizes_known: bool,
+}
+
+fn decimal(value: Option<&Value>) -> Option<Decimal> {
+    match value? {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+/// `[{"price": "0.48", "size": "120"}, ...]`
+fn parse_levels(levels: &Value) -> Option<Vec<(Decimal, Decimal)>> {
+    levels
+        .as_array()?
+        .iter()
+        .map(|level| Some((decimal(level.get("price"))?, decimal(level.get("size"))?)))
+        .collect()
+}
+
+/// Full depth (`book` events: `asset_id` + `bids`/`asks` levels) or
+/// top of book (`token_id` + `bid`/`ask`, sizes in `bid_size`/`ask_size`
+/// when the feed sends them). Levels are ordered best first.
+fn parse_book(json: &Value) -> Option<BookMessage> {
+    let token_id = json
+        .get("asset_id")
+        .or_else(|| json.get("token_id"))?
+        .as_str()?
+        .to_string();
+
+    if let (Some(bids), Some(asks)) = (json.get("bids"), json.get("asks")) {
+        let mut bids = parse_levels(bids)?;
+        let mut asks = parse_levels(asks)?;
+        bids.sort_by_key(|&(price, _)| std::cmp::Reverse(price));
+        asks.sort_by_key(|&(price, _)| price);
+
+        return Some(BookMessage {
+            token_id,
+            bids,
+            asks,
+            sizes_known: true,
+        });
+    }
+
+    let bid = decimal(json.get("bid"));
+    let ask = decimal(json.get("ask"));
+    let bid_size = decimal(json.get("bid_size"));
+    let ask_size = decimal(json.get("ask_size"));
+
+    // A side without a reported size carries a placeholder of 1
+    let sizes_known = (bid.is_none() || bid_size.is_some()) && (ask.is_none() || ask_size.is_some());
+
+    Some(BookMessage {
+        token_id,
+        bids: bid.map(|b| vec![(b, bid_size.unwrap_or(Decimal::ONE))]).unwrap_or_default(),
+        asks: ask.map(|a| vec![(a, ask_size.unwrap_or(Decimal::ONE))]).unwrap_or_default(),
+        sizes_known,
+    })
+}